@@ -0,0 +1,295 @@
+//! Copy DICOM files.
+
+use std::io::{ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tracing::{debug, error, warn};
+
+use super::copy_times;
+
+/// Errors that can occur while copying a DICOM file.
+#[derive(thiserror::Error, Debug)]
+pub enum CopyError {
+    #[error("Unable to create directory: {0:#?}")]
+    CreateDir(PathBuf, #[source] std::io::Error),
+    #[error("IO error while copying {0:#?} to {1:#?}")]
+    IO(PathBuf, PathBuf, #[source] std::io::Error),
+    #[error("Copied file {1:#?} differs from the source file {0:#?}")]
+    VerifyMismatch(PathBuf, PathBuf),
+}
+
+/// Options that control how a DICOM file is copied.
+#[derive(Clone, Debug, Default)]
+pub struct CopyOptions {
+    /// Number of times the copy is retried while the source or destination is busy.
+    pub retries: usize,
+    /// Time to wait before a copy is retried.
+    pub retry_delay: Duration,
+    /// Re-read the copied file and compare it with the source file.
+    pub verify: bool,
+    /// Set the modification and access time of the copied file to those of the source file.
+    pub preserve_times: bool,
+    /// Copy to a temporary file next to the destination, and rename it once the copy is
+    /// complete. An interrupted copy never leaves a partial file at the destination.
+    pub atomic: bool,
+}
+
+/// Copy a DICOM file.
+///
+/// The parent directory of the destination is created if it doesn't exist.
+/// If verification is requested and the copy differs from the source, the copy is removed.
+/// For an atomic copy, the temporary file is removed if any step of the copy fails.
+///
+/// # Arguments
+///
+/// * `src`: source file
+/// * `dst`: destination file
+/// * `options`: options to control the copy
+///
+/// returns: Result<(), CopyError>
+pub fn copy_dicom<P, Q>(src: P, dst: Q, options: &CopyOptions) -> Result<(), CopyError>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let src = src.as_ref();
+    let dst = dst.as_ref();
+    if let Some(parent) = dst.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| CopyError::CreateDir(parent.to_path_buf(), e))?;
+    }
+    let target = if options.atomic {
+        let file_name = dst.file_name().unwrap_or_default().to_string_lossy();
+        dst.with_file_name(format!(".{}.part", file_name))
+    } else {
+        dst.to_path_buf()
+    };
+
+    let r = copy_to_target(src, dst, &target, options);
+    if r.is_err() && options.atomic && target.exists() {
+        if let Err(e) = std::fs::remove_file(&target) {
+            error!("Unable to remove {target:#?}: {e:#?}");
+        }
+    }
+    r
+}
+
+/// Copy a file to the target path, and move it to the destination if the copy is atomic.
+fn copy_to_target(
+    src: &Path,
+    dst: &Path,
+    target: &Path,
+    options: &CopyOptions,
+) -> Result<(), CopyError> {
+    let io_error = |e| CopyError::IO(src.to_path_buf(), dst.to_path_buf(), e);
+    debug!("Copying {:#?} -> {:#?}", src, target);
+    retry_on_busy(options.retries, options.retry_delay, || {
+        std::fs::copy(src, target)
+    })
+    .map_err(io_error)?;
+    if options.verify && !files_equal(src, target).map_err(io_error)? {
+        error!("Copying {src:#?} to {dst:#?} failed: copy differs from the source");
+        if let Err(e) = std::fs::remove_file(target) {
+            error!("Unable to remove {target:#?}: {e:#?}");
+        }
+        return Err(CopyError::VerifyMismatch(
+            src.to_path_buf(),
+            dst.to_path_buf(),
+        ));
+    }
+    if options.preserve_times {
+        copy_times(src, target).map_err(io_error)?;
+    }
+    if options.atomic {
+        std::fs::rename(target, dst).map_err(io_error)?;
+    }
+    Ok(())
+}
+
+/// Run an IO operation, and retry it while it fails because a resource is busy.
+///
+/// # Arguments
+///
+/// * `retries`: maximum number of retries
+/// * `delay`: time to wait before each retry
+/// * `f`: IO operation
+///
+/// returns: std::io::Result<T>
+/// The result of the first attempt that doesn't fail with `ErrorKind::ResourceBusy`, or of the
+/// last retry.
+pub fn retry_on_busy<T, F>(retries: usize, delay: Duration, mut f: F) -> std::io::Result<T>
+where
+    F: FnMut() -> std::io::Result<T>,
+{
+    let mut attempt = 0;
+    loop {
+        match f() {
+            Err(e) if e.kind() == ErrorKind::ResourceBusy && attempt < retries => {
+                attempt += 1;
+                warn!("Resource busy, retrying ({}/{}): {}", attempt, retries, e);
+                std::thread::sleep(delay);
+            }
+            r => return r,
+        }
+    }
+}
+
+/// Check if two files have the same content.
+///
+/// # Arguments
+///
+/// * `a`: path to a file
+/// * `b`: path to a file
+///
+/// returns: std::io::Result<bool>
+/// Returns true if both files have the same byte content, otherwise false.
+/// An error is returned if one of the files couldn't be read.
+pub fn files_equal<P, Q>(a: P, b: Q) -> std::io::Result<bool>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let mut fa = std::io::BufReader::new(std::fs::File::open(a)?);
+    let mut fb = std::io::BufReader::new(std::fs::File::open(b)?);
+    if fa.get_ref().metadata()?.len() != fb.get_ref().metadata()?.len() {
+        return Ok(false);
+    }
+    let mut buf_a = [0u8; 8192];
+    let mut buf_b = [0u8; 8192];
+    loop {
+        let n = fa.read(&mut buf_a)?;
+        if n == 0 {
+            return Ok(true);
+        }
+        fb.read_exact(&mut buf_b[..n])?;
+        if buf_a[..n] != buf_b[..n] {
+            return Ok(false);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use super::{copy_dicom, files_equal, retry_on_busy, CopyError, CopyOptions};
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join("rad_tools_common_copy")
+            .join(name);
+        if dir.is_dir() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn copy_dicom_create_dir() {
+        let dir = test_dir("create_dir");
+        let src = dir.join("src.dcm");
+        let dst = dir.join("a").join("b").join("dst.dcm");
+        std::fs::write(&src, [1u8, 2, 3, 4]).unwrap();
+
+        copy_dicom(&src, &dst, &CopyOptions::default()).unwrap();
+        assert_eq!(std::fs::read(&src).unwrap(), std::fs::read(&dst).unwrap());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn copy_dicom_verify_atomic() {
+        let dir = test_dir("verify_atomic");
+        let src = dir.join("src.dcm");
+        let dst = dir.join("out").join("dst.dcm");
+        std::fs::write(&src, [1u8, 2, 3, 4]).unwrap();
+
+        let options = CopyOptions {
+            verify: true,
+            atomic: true,
+            ..Default::default()
+        };
+        copy_dicom(&src, &dst, &options).unwrap();
+        assert_eq!(std::fs::read(&src).unwrap(), std::fs::read(&dst).unwrap());
+        // Only the destination file remains, the temporary file is renamed.
+        assert_eq!(1, std::fs::read_dir(dir.join("out")).unwrap().count());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_dicom_atomic_error_removes_temp() {
+        let dir = test_dir("atomic_error");
+        let src = dir.join("src.dcm");
+        let dst = dir.join("out").join("dst.dcm");
+        std::fs::write(&src, [1u8, 2, 3, 4]).unwrap();
+        // Everything written to the temporary file is discarded, so the verification fails.
+        std::fs::create_dir_all(dir.join("out")).unwrap();
+        std::os::unix::fs::symlink("/dev/null", dir.join("out").join(".dst.dcm.part")).unwrap();
+
+        let options = CopyOptions {
+            verify: true,
+            atomic: true,
+            ..Default::default()
+        };
+        let r = copy_dicom(&src, &dst, &options);
+        assert!(matches!(r, Err(CopyError::VerifyMismatch(_, _))));
+        assert_eq!(0, std::fs::read_dir(dir.join("out")).unwrap().count());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn files_equal_mismatch() {
+        let dir = test_dir("files_equal");
+        let a = dir.join("a.bin");
+        let b = dir.join("b.bin");
+        let c = dir.join("c.bin");
+        std::fs::write(&a, [1u8, 2, 3, 4]).unwrap();
+        std::fs::write(&b, [1u8, 2, 3, 4]).unwrap();
+        std::fs::write(&c, [1u8, 2, 0, 4]).unwrap();
+
+        assert!(files_equal(&a, &b).unwrap());
+        assert!(!files_equal(&a, &c).unwrap());
+        assert!(files_equal(&a, dir.join("d.bin")).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn retry_on_busy_retries() {
+        let mut attempts = 0;
+        let r = retry_on_busy(3, Duration::ZERO, || {
+            attempts += 1;
+            if attempts < 3 {
+                Err(std::io::Error::from(ErrorKind::ResourceBusy))
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(3, r.unwrap());
+
+        // Other errors aren't retried.
+        let mut attempts = 0;
+        let r: std::io::Result<()> = retry_on_busy(3, Duration::ZERO, || {
+            attempts += 1;
+            Err(std::io::Error::from(ErrorKind::NotFound))
+        });
+        assert_eq!(ErrorKind::NotFound, r.unwrap_err().kind());
+        assert_eq!(1, attempts);
+
+        // The last error is returned once the retries are exhausted.
+        let mut attempts = 0;
+        let r: std::io::Result<()> = retry_on_busy(2, Duration::ZERO, || {
+            attempts += 1;
+            Err(std::io::Error::from(ErrorKind::ResourceBusy))
+        });
+        assert_eq!(ErrorKind::ResourceBusy, r.unwrap_err().kind());
+        assert_eq!(3, attempts);
+    }
+}
@@ -0,0 +1,192 @@
+//! File system helpers.
+
+mod copy;
+
+pub use copy::{copy_dicom, files_equal, retry_on_busy, CopyError, CopyOptions};
+
+use sha2::{Digest, Sha256};
+use std::fs::{File, FileTimes};
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use tracing::{debug, error};
+
+/// Counter to generate unique probe file names within a process.
+static PROBE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Check if a directory is writable.
+///
+/// The check attempts to create and remove a temporary file in the directory.
+/// Unlike inspecting the readonly flag of the permissions, this also takes access control
+/// lists (e.g. on Windows) into account.
+///
+/// # Arguments
+///
+/// * `p`: path to directory
+///
+/// returns: bool
+/// Returns true if a file could be created in the directory, otherwise false.
+pub fn is_writable<P>(p: P) -> bool
+where
+    P: AsRef<Path>,
+{
+    let p = p.as_ref();
+    if !p.is_dir() {
+        error!("Path is not a directory {:#?}", p);
+        return false;
+    }
+    let probe = p.join(format!(
+        ".rad_tools_write_probe_{}_{}",
+        process::id(),
+        PROBE_COUNTER.fetch_add(1, Ordering::SeqCst)
+    ));
+    match std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)
+    {
+        Ok(f) => {
+            drop(f);
+            if let Err(e) = std::fs::remove_file(&probe) {
+                error!("Couldn't remove write probe {:#?}: {:#?}", probe, e);
+            }
+            true
+        }
+        Err(e) => {
+            debug!("Directory {:#?} is not writable: {:#?}", p, e);
+            false
+        }
+    }
+}
+
+/// Copy the modification and access time of a file to another file.
+///
+/// # Arguments
+///
+/// * `src`: file from which the times are read
+/// * `dst`: file of which the times are set
+///
+/// returns: std::io::Result<()>
+/// An error is returned if the times of `src` can't be read or the times of `dst` can't be set.
+pub fn copy_times<P, Q>(src: P, dst: Q) -> std::io::Result<()>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let meta = std::fs::metadata(src.as_ref())?;
+    let times = FileTimes::new()
+        .set_accessed(meta.accessed()?)
+        .set_modified(meta.modified()?);
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(dst.as_ref())?
+        .set_times(times)
+}
+
+/// Compute the SHA-256 checksum of a file.
+///
+/// # Arguments
+///
+/// * `p`: file path
+///
+/// returns: std::io::Result<String>
+/// The checksum as a lowercase hexadecimal string.
+pub fn sha256<P>(p: P) -> std::io::Result<String>
+where
+    P: AsRef<Path>,
+{
+    let mut file = File::open(p)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{copy_times, is_writable, sha256};
+    use std::fs::FileTimes;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn is_writable_tempdir() {
+        let dir = std::env::temp_dir().join("rad_tools_common_is_writable");
+        if dir.is_dir() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        std::fs::create_dir_all(&dir).unwrap();
+
+        assert!(is_writable(&dir));
+        assert_eq!(0, std::fs::read_dir(&dir).unwrap().count());
+        assert!(!is_writable(dir.join("does_not_exist")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn is_writable_readonly_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join("rad_tools_common_is_writable_readonly");
+        if dir.is_dir() {
+            std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o555)).unwrap();
+
+        // Root ignores the permission bits, so only check when the directory really is read-only.
+        let expected = std::fs::File::create(dir.join("probe")).is_ok();
+        assert_eq!(expected, is_writable(&dir));
+
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o755)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn copy_times_modified() {
+        let dir = std::env::temp_dir().join("rad_tools_common_copy_times");
+        if dir.is_dir() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        std::fs::create_dir_all(&dir).unwrap();
+        let src = dir.join("src.txt");
+        let dst = dir.join("dst.txt");
+        std::fs::write(&src, "src").unwrap();
+        std::fs::write(&dst, "dst").unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        std::fs::File::options()
+            .write(true)
+            .open(&src)
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(modified))
+            .unwrap();
+
+        copy_times(&src, &dst).unwrap();
+        assert_eq!(
+            modified,
+            std::fs::metadata(&dst).unwrap().modified().unwrap()
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn sha256_file() {
+        let dir = std::env::temp_dir().join("rad_tools_common_sha256");
+        if dir.is_dir() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        std::fs::create_dir_all(&dir).unwrap();
+        let p = dir.join("abc.txt");
+        std::fs::write(&p, "abc").unwrap();
+
+        assert_eq!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            sha256(&p).unwrap()
+        );
+        assert!(sha256(dir.join("does_not_exist")).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,95 @@
+//! Functionality shared between the rad-tools applications.
+
+pub mod fs;
+
+#[doc(hidden)]
+pub use tracing;
+
+/// Get the log level from the `verbose`, `debug` and `trace` flags of a command line interface.
+///
+/// The expression must have the boolean fields `verbose`, `debug` and `trace`.
+/// The most detailed level of the enabled flags takes precedence: `trace` > `debug` > `verbose`
+/// (`INFO`). If none of the flags is enabled, the default level is returned, which is `WARN`
+/// unless specified otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use rad_tools_common::get_log_level;
+/// use tracing::Level;
+///
+/// struct Cli {
+///     verbose: bool,
+///     debug: bool,
+///     trace: bool,
+/// }
+///
+/// let cli = Cli { verbose: true, debug: false, trace: false };
+/// assert_eq!(Level::INFO, get_log_level!(cli));
+/// let cli = Cli { verbose: false, debug: false, trace: false };
+/// assert_eq!(Level::ERROR, get_log_level!(cli, Level::ERROR));
+/// ```
+#[macro_export]
+macro_rules! get_log_level {
+    ($cli:expr) => {
+        $crate::get_log_level!($cli, $crate::tracing::Level::WARN)
+    };
+    ($cli:expr, $default:expr) => {{
+        let cli = &$cli;
+        if cli.trace {
+            $crate::tracing::Level::TRACE
+        } else if cli.debug {
+            $crate::tracing::Level::DEBUG
+        } else if cli.verbose {
+            $crate::tracing::Level::INFO
+        } else {
+            $default
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing::Level;
+
+    struct Cli {
+        verbose: bool,
+        debug: bool,
+        trace: bool,
+    }
+
+    fn cli(verbose: bool, debug: bool, trace: bool) -> Cli {
+        Cli {
+            verbose,
+            debug,
+            trace,
+        }
+    }
+
+    #[test]
+    fn get_log_level() {
+        assert_eq!(Level::WARN, get_log_level!(cli(false, false, false)));
+        assert_eq!(Level::INFO, get_log_level!(cli(true, false, false)));
+        assert_eq!(Level::DEBUG, get_log_level!(cli(false, true, false)));
+        assert_eq!(Level::TRACE, get_log_level!(cli(false, false, true)));
+        assert_eq!(Level::DEBUG, get_log_level!(cli(true, true, false)));
+        assert_eq!(Level::TRACE, get_log_level!(cli(true, true, true)));
+        assert_eq!(Level::TRACE, get_log_level!(cli(false, true, true)));
+    }
+
+    #[test]
+    fn get_log_level_default() {
+        assert_eq!(
+            Level::ERROR,
+            get_log_level!(cli(false, false, false), Level::ERROR)
+        );
+        assert_eq!(
+            Level::INFO,
+            get_log_level!(cli(true, false, false), Level::ERROR)
+        );
+        assert_eq!(
+            Level::TRACE,
+            get_log_level!(cli(false, false, true), Level::ERROR)
+        );
+    }
+}
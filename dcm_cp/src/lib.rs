@@ -2,6 +2,8 @@ use dicom_object::ReadError;
 use log::debug;
 use pathdiff::diff_paths;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use tracing::{error, trace};
 use walkdir::WalkDir;
 
@@ -35,22 +37,141 @@ pub enum DcmcpError {
     IO(#[from] std::io::Error),
     #[error("Unable to write to destination: {0:#?}")]
     DestinationNotWritable(std::path::PathBuf),
+    #[error("Copied file {1:#?} differs from the source file {0:#?}")]
+    VerifyMismatch(std::path::PathBuf, std::path::PathBuf),
+    #[error("Unable to read the data to sort {0:#?}: {1}")]
+    SortData(std::path::PathBuf, dcm_sort::FromDicomObjectError),
+    #[error("Unable to determine the sorted destination of {0:#?}: {1}")]
+    SortPath(std::path::PathBuf, dcm_sort::Error),
 }
 
 pub type DcmResult<T> = Result<T, Box<DcmcpError>>;
 pub type DcmResults<T> = Result<T, Vec<Box<DcmcpError>>>;
 
+/// Defines how the patient ID read from a DICOM file is compared with the requested patient ID.
+///
+/// Trailing padding of the patient ID in the DICOM file is always ignored. By default, the
+/// patient IDs are compared exactly.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct PatientIdMatch {
+    /// Ignore leading and trailing whitespace.
+    pub trim: bool,
+    /// Ignore differences in case.
+    pub ignore_case: bool,
+}
+
+impl PatientIdMatch {
+    /// Check if two patient IDs match.
+    ///
+    /// # Arguments
+    ///
+    /// * `a`: patient ID
+    /// * `b`: patient ID
+    ///
+    /// returns: bool
+    /// Returns true if the patient IDs are equal after applying the matching rules, otherwise false.
+    pub fn matches(&self, a: &str, b: &str) -> bool {
+        let (a, b) = if self.trim {
+            (a.trim(), b.trim())
+        } else {
+            (a, b)
+        };
+        if self.ignore_case {
+            a.to_lowercase() == b.to_lowercase()
+        } else {
+            a == b
+        }
+    }
+}
+
+/// Defines where the copied DICOM files are placed in the output directory.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Layout {
+    /// Mirror the directory tree of the input directory.
+    #[default]
+    Mirror,
+    /// Place the files in the patient/study/series tree used by `dcm_sort`, see
+    /// [`dcm_sort::to_path_buf`].
+    Sorted,
+}
+
+/// Options that control how the DICOM files are copied.
+#[derive(Clone, Debug, Default)]
+pub struct DcmcpOptions {
+    /// When set to `true`, copying stops after the file that's currently being processed.
+    pub stop: Arc<AtomicBool>,
+    /// Rules used to compare the patient IDs.
+    pub patient_id_match: PatientIdMatch,
+    /// Follow symbolic links while walking the input directories.
+    ///
+    /// Symbolic links that point to one of their own parent directories are reported as an
+    /// error and not followed, which avoids infinite loops.
+    pub follow_symlinks: bool,
+    /// Besides the patient ID, also match the patient ID against the Other Patient IDs (0010,1000).
+    pub also_match_other_ids: bool,
+    /// Stop after this number of DICOM files are copied.
+    pub limit: Option<usize>,
+    /// Only report the files that would be copied, without creating directories or copying.
+    pub dry_run: bool,
+    /// Set the modification and access time of each copied file to those of the source file.
+    pub preserve_times: bool,
+    /// Re-read each copied file and compare it with the source file.
+    pub verify: bool,
+    /// Where the copied files are placed in the output directory.
+    pub layout: Layout,
+}
+
+impl DcmcpOptions {
+    /// Check if a stop was requested.
+    fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::SeqCst)
+    }
+
+    /// Check if the maximum number of files to copy is reached.
+    fn is_limit_reached(&self, ncopied: usize) -> bool {
+        self.limit.is_some_and(|limit| ncopied >= limit)
+    }
+}
+
 /// Copy a DICOM file(s) and or directories to a destination directory if the patient ID matches.
 ///
 /// # Arguments
 ///
 /// * `input`: input file
 /// * `output`: output directory
-pub fn dcm_cp_files(inputs: &[String], output: &str, patient_id: &str) -> DcmResults<()> {
+/// * `patient_id`: patient ID to match
+/// * `options`: options to control the copy
+///
+/// returns: DcmResults<usize>
+/// The number of copied DICOM files is returned, otherwise the detected errors.
+pub fn dcm_cp_files(
+    inputs: &[String],
+    output: &str,
+    patient_id: &str,
+    options: &DcmcpOptions,
+) -> DcmResults<usize> {
     let mut errors = Vec::new();
+    let mut ncopied = 0;
     for input in inputs {
-        match dcm_cp_file(input, output, patient_id) {
-            Ok(_) => {}
+        if options.is_stopped() {
+            debug!("Copying stopped before processing {:#?}", input);
+            break;
+        }
+        if options.is_limit_reached(ncopied) {
+            debug!(
+                "Copying stopped before processing {:#?}: limit reached",
+                input
+            );
+            break;
+        }
+        let input_options = DcmcpOptions {
+            limit: options.limit.map(|limit| limit - ncopied),
+            ..options.clone()
+        };
+        match dcm_cp_file(input, output, patient_id, &input_options) {
+            Ok(n) => {
+                ncopied += n;
+            }
             Err(e) => {
                 errors.extend(e);
             }
@@ -59,7 +180,7 @@ pub fn dcm_cp_files(inputs: &[String], output: &str, patient_id: &str) -> DcmRes
     if !errors.is_empty() {
         Err(errors)
     } else {
-        Ok(())
+        Ok(ncopied)
     }
 }
 
@@ -70,8 +191,18 @@ pub fn dcm_cp_files(inputs: &[String], output: &str, patient_id: &str) -> DcmRes
 /// * `input`: input file
 /// * `output`: output directory
 /// * `patient_id`: patient ID to match
+/// * `options`: options to control the copy
 ///
-pub fn dcm_cp_file(input: &str, output: &str, patient_id: &str) -> DcmResults<()> {
+/// returns: DcmResults<usize>
+/// The number of copied DICOM files is returned, otherwise the detected errors.
+/// When `input` is a directory, files of other patients and files that can't be read as DICOM
+/// are skipped without an error.
+pub fn dcm_cp_file(
+    input: &str,
+    output: &str,
+    patient_id: &str,
+    options: &DcmcpOptions,
+) -> DcmResults<usize> {
     let input_path = Path::new(input);
     let mut errs = vec![];
     if !input_path.exists() {
@@ -103,10 +234,10 @@ pub fn dcm_cp_file(input: &str, output: &str, patient_id: &str) -> DcmResults<()
     }
 
     let dcm_cp = |input_path: &Path, output_dir_path: &Path, patient_id: &str| {
-        match internal::dcm_cp_file(input_path, output_dir_path, patient_id) {
-            Ok(_) => Ok(()),
+        match internal::dcm_cp_file(input_path, output_dir_path, patient_id, options) {
+            Ok(_) => Ok(1),
             Err(e) => match *e {
-                DcmcpError::PatientIdNotFound(_) => Ok(()),
+                DcmcpError::PatientIdNotFound(_) => Ok(0),
                 e => {
                     // error!("{:#?}", e);
                     Err(Box::new(e))
@@ -116,21 +247,39 @@ pub fn dcm_cp_file(input: &str, output: &str, patient_id: &str) -> DcmResults<()
     };
 
     if input_path.is_file() {
+        // A single input file is copied directly into the output directory.
+        // There is no directory tree to mirror, so no relative path is computed.
         debug!("Input path [{:#?}] is a file", input_path);
-        match dcm_cp(input_path, output_dir_path, patient_id) {
-            Ok(_) => {}
+        if options.is_limit_reached(0) {
+            return Ok(0);
+        }
+        return match dcm_cp(input_path, output_dir_path, patient_id) {
+            Ok(n) => Ok(n),
             Err(e) => {
                 errs.push(e);
-                return Err(errs);
+                Err(errs)
             }
-        }
-    } else if input_path.is_dir() {
-        let entries = WalkDir::new(input_path);
+        };
+    }
+    let mut ncopied = 0;
+    if input_path.is_dir() {
+        let entries = WalkDir::new(input_path).follow_links(options.follow_symlinks);
         for entry in entries {
+            if options.is_stopped() {
+                debug!("Copying stopped while walking {:#?}", input_path);
+                break;
+            }
+            if options.is_limit_reached(ncopied) {
+                debug!(
+                    "Copying stopped while walking {:#?}: limit reached",
+                    input_path
+                );
+                break;
+            }
             if entry.is_err() {
                 errs.push(Box::new(DcmcpError::WalkDirIter(
                     input_path.to_path_buf(),
-                    entry.err().unwrap()
+                    entry.err().unwrap(),
                 )));
                 continue;
             }
@@ -159,47 +308,149 @@ pub fn dcm_cp_file(input: &str, output: &str, patient_id: &str) -> DcmResults<()
                 output_dir_path,
                 rel_path
             );
-            let output_path = output_dir_path.join(rel_path);
+            let output_path = match options.layout {
+                Layout::Mirror => output_dir_path.join(rel_path),
+                Layout::Sorted => output_dir_path.to_path_buf(),
+            };
             match dcm_cp(entry_path, &output_path, patient_id) {
-                Ok(_) => {}
-                Err(e) => {
-                    errs.push(e);
+                Ok(n) => {
+                    ncopied += n;
                 }
+                Err(e) => match *e {
+                    // Files of other patients and files that aren't DICOM are skipped.
+                    DcmcpError::PatientIdNoMatch(_) | DcmcpError::ReadData(_, _) => {
+                        debug!("Skipping {:#?}: {}", entry_path, e);
+                    }
+                    e => {
+                        errs.push(Box::new(e));
+                    }
+                },
             }
         }
     }
-    Ok(())
+    if !errs.is_empty() {
+        Err(errs)
+    } else {
+        Ok(ncopied)
+    }
 }
 
 mod internal {
-    use crate::{DcmResult, DcmcpError};
-    use dicom_dictionary_std::tags::{ISSUER_OF_PATIENT_ID, PATIENT_ID};
+    use crate::{DcmResult, DcmcpError, DcmcpOptions, Layout};
+    use dcm_sort::{Data, TryFromDicomObject};
+    // Other Patient IDs is retired in the DICOM standard, but it's still set by many systems.
+    #[allow(deprecated)]
+    use dicom_dictionary_std::tags::OTHER_PATIENT_I_DS;
+    use dicom_dictionary_std::tags::{
+        ISSUER_OF_PATIENT_ID, OTHER_PATIENT_NAMES, PATIENT_ID, PIXEL_DATA,
+    };
     use dicom_object::file::ReadPreamble;
-    use dicom_object::{InMemDicomObject, OpenFileOptions};
+    use dicom_object::{DefaultDicomObject, InMemDicomObject, OpenFileOptions};
     use log::{debug, error, info, trace, warn};
+    use rad_tools_common::fs::{copy_dicom, is_writable, CopyError, CopyOptions};
+    use std::path::PathBuf;
 
-    /// Read the patient ID from a DICOM file
+    /// Read a DICOM file up to the tags needed to copy it.
     ///
     /// # Arguments
     ///
     /// * `p`: DICOM file
+    /// * `options`: options to control the copy
     ///
-    /// returns: Result<String, DcmcpError>
-    /// If the patient ID could be read, it's returned otherwise the error is returned.
-    fn read_patient_id_from_file<P>(p: P) -> DcmResult<String>
+    /// returns: Result<DefaultDicomObject, DcmcpError>
+    /// The patient IDs are always read. The Other Patient IDs are read if they need to be
+    /// matched, and everything up to the pixel data is read if the files are sorted.
+    fn read_file<P>(p: P, options: &DcmcpOptions) -> DcmResult<DefaultDicomObject>
     where
         P: AsRef<std::path::Path>,
     {
         let p = p.as_ref();
+        let read_until = if options.layout == Layout::Sorted {
+            PIXEL_DATA
+        } else if options.also_match_other_ids {
+            OTHER_PATIENT_NAMES
+        } else {
+            ISSUER_OF_PATIENT_ID
+        };
         let open_file_options = OpenFileOptions::new()
             .read_preamble(ReadPreamble::default())
-            .read_until(ISSUER_OF_PATIENT_ID);
-        let r = open_file_options.open_file(p);
-        if let Err(e) = r {
-            return Err(Box::new(DcmcpError::ReadData(p.to_path_buf(), e)));
+            .read_until(read_until);
+        open_file_options
+            .open_file(p)
+            .map_err(|e| Box::new(DcmcpError::ReadData(p.to_path_buf(), e)))
+    }
+
+    /// Get the patient IDs from a DICOM object.
+    ///
+    /// # Arguments
+    ///
+    /// * `obj`: in memory DICOM object
+    /// * `p`: path from where the DICOM object was orignally read
+    /// * `also_match_other_ids`: also get the Other Patient IDs
+    ///
+    /// returns: Result<Vec<String>, DcmcpError>
+    /// If the patient ID could be read, it's returned first, followed by the other patient IDs
+    /// (if requested). Otherwise the error is returned.
+    fn get_patient_ids_from_obj<P>(
+        obj: &InMemDicomObject,
+        p: P,
+        also_match_other_ids: bool,
+    ) -> DcmResult<Vec<String>>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let mut ids = vec![get_patient_id_from_obj(obj, p)?];
+        if also_match_other_ids {
+            ids.extend(get_other_patient_ids_from_obj(obj));
+        }
+        Ok(ids)
+    }
+
+    /// Get the sorted destination directory of a DICOM object, see [`dcm_sort::to_path_buf`].
+    ///
+    /// # Arguments
+    ///
+    /// * `obj`: in memory DICOM object
+    /// * `p`: path from where the DICOM object was orignally read
+    /// * `dst`: output directory
+    ///
+    /// returns: Result<PathBuf, DcmcpError>
+    fn get_sorted_dst_from_obj<P, Q>(obj: &InMemDicomObject, p: P, dst: Q) -> DcmResult<PathBuf>
+    where
+        P: AsRef<std::path::Path>,
+        Q: AsRef<std::path::Path>,
+    {
+        let p = p.as_ref();
+        let data = Data::try_from_dicom_obj(obj)
+            .map_err(|e| Box::new(DcmcpError::SortData(p.to_path_buf(), e)))?;
+        dcm_sort::to_path_buf(&data, dst)
+            .map_err(|e| Box::new(DcmcpError::SortPath(p.to_path_buf(), e)))
+    }
+
+    /// Get the other patient IDs (0010,1000) from a DICOM object.
+    ///
+    /// # Arguments
+    ///
+    /// * `obj`: in memory DICOM object
+    ///
+    /// returns: Vec<String>
+    /// The other patient IDs, or an empty vector if they are absent or can't be read.
+    #[allow(deprecated)]
+    fn get_other_patient_ids_from_obj(obj: &InMemDicomObject) -> Vec<String> {
+        match obj.element_opt(OTHER_PATIENT_I_DS) {
+            Ok(Some(elem)) => match elem.to_str() {
+                Ok(s) => s
+                    .split('\\')
+                    .map(|id| id.trim_end().to_string())
+                    .filter(|id| !id.is_empty())
+                    .collect(),
+                Err(e) => {
+                    debug!("Unable to read the other patient IDs: {:#?}", e);
+                    vec![]
+                }
+            },
+            _ => vec![],
         }
-        let obj = r.unwrap();
-        get_patient_id_from_obj(&obj, p)
     }
 
     /// Get the patient ID from a DICOM object.
@@ -236,10 +487,16 @@ mod internal {
     /// # Arguments
     ///
     /// * `src`: source file
-    /// * `dst`: destination directory
+    /// * `dst`: destination directory, or the output directory if the files are sorted
     /// * `patient_id`: patient ID to match
+    /// * `options`: options to control the copy
     ///
-    pub(crate) fn dcm_cp_file<P>(src: P, dst: P, patient_id: &str) -> DcmResult<()>
+    pub(crate) fn dcm_cp_file<P>(
+        src: P,
+        dst: P,
+        patient_id: &str,
+        options: &DcmcpOptions,
+    ) -> DcmResult<()>
     where
         P: AsRef<std::path::Path>,
     {
@@ -251,10 +508,31 @@ mod internal {
             return Err(Box::new(DcmcpError::InputNotFile(src.to_path_buf())));
         }
 
-        let pt_id = read_patient_id_from_file(src)?;
-        if pt_id != patient_id {
+        let obj = read_file(src, options)?;
+        let pt_ids = get_patient_ids_from_obj(&obj, src, options.also_match_other_ids)?;
+        if !pt_ids
+            .iter()
+            .any(|pt_id| options.patient_id_match.matches(pt_id, patient_id))
+        {
+            if options.dry_run {
+                info!("Dry run: not copying {src:#?}, patient ID {pt_ids:?} doesn't match");
+            }
             return Err(Box::new(DcmcpError::PatientIdNoMatch(src.to_path_buf())));
         }
+        let sorted_dst = match options.layout {
+            Layout::Mirror => None,
+            Layout::Sorted => Some(get_sorted_dst_from_obj(&obj, src, dst)?),
+        };
+        let dst = sorted_dst.as_deref().unwrap_or(dst);
+
+        if options.dry_run {
+            let ofile = dst.join(src.file_name().unwrap());
+            info!(
+                "Dry run: copying {:#?} -> {:#?} [patient ID matches]",
+                src, &ofile
+            );
+            return Ok(());
+        }
 
         // Only create the output directory if the file is a DICOM file.
         if !dst.is_dir() {
@@ -270,7 +548,7 @@ mod internal {
                 dst.to_path_buf(),
             )));
         }
-        if !is_dir_writable(&dst) {
+        if !is_writable(dst) {
             error!(
             "Copying {src:#?} to {dst:#?} is not possible: destination directory is not writable"
         );
@@ -281,35 +559,19 @@ mod internal {
 
         let ofile = dst.join(src.file_name().unwrap());
         info!("Copying {:#?} -> {:#?}", src, &ofile);
-        let _ = std::fs::copy(src, ofile).map_err(|e| Box::new(DcmcpError::IO(e)));
-        Ok(())
-    }
-
-    /// Check if a directory is writable.
-    ///
-    /// # Arguments
-    ///
-    /// * `p`: path to directory
-    ///
-    /// returns: bool
-    /// Returns true if the directory is writable, otherwise false.
-    /// Function doesn't check access control using ACL on Windows.
-    fn is_dir_writable<P>(p: &P) -> bool
-    where
-        P: AsRef<std::path::Path>,
-    {
-        let p = p.as_ref();
-        if !p.is_dir() {
-            error!("Path is not a directory {:#?}", p);
-            return false;
-        }
-        match std::fs::metadata(p) {
-            Ok(m) => !m.permissions().readonly(),
-            Err(e) => {
-                error!("Couldn't get metadata for {:#?}: {:#?}", p, e);
-                false
+        let copy_options = CopyOptions {
+            verify: options.verify,
+            preserve_times: options.preserve_times,
+            ..Default::default()
+        };
+        copy_dicom(src, &ofile, &copy_options).map_err(|e| match e {
+            CopyError::CreateDir(p, _) => {
+                Box::new(DcmcpError::UnableToCreateDestinationDirectory(p))
             }
-        }
+            CopyError::IO(_, _, e) => Box::new(DcmcpError::IO(e)),
+            CopyError::VerifyMismatch(src, dst) => Box::new(DcmcpError::VerifyMismatch(src, dst)),
+        })?;
+        Ok(())
     }
 
     #[cfg(test)]
@@ -321,6 +583,8 @@ mod internal {
         use log::{trace, LevelFilter};
         use std::path::PathBuf;
 
+        use crate::DcmcpOptions;
+
         fn init_logger() {
             let _ = env_logger::builder()
                 .is_test(true)
@@ -376,7 +640,7 @@ mod internal {
             obj.put_str(PATIENT_NAME, VR::PN, "Last^First");
 
             let temp_dir = std::env::temp_dir();
-            assert!(super::is_dir_writable(&temp_dir));
+            assert!(rad_tools_common::fs::is_writable(&temp_dir));
 
             let filename = "test_dcmcp.dcm";
 
@@ -407,7 +671,7 @@ mod internal {
             assert!(tmp_input.is_file());
 
             // Copy the temporary DICOM file
-            super::dcm_cp_file(&tmp_input, &tmp_out_dir, s).unwrap();
+            super::dcm_cp_file(&tmp_input, &tmp_out_dir, s, &DcmcpOptions::default()).unwrap();
 
             // Check the copied file exists and compare the byte content to ensure it's the same data
             assert!(tmp_output.is_file());
@@ -425,12 +689,18 @@ mod internal {
 
 #[cfg(test)]
 mod tests {
-    use crate::DcmcpError;
+    use crate::{DcmcpError, DcmcpOptions, Layout, PatientIdMatch};
     use dicom_core::VR;
-    use dicom_dictionary_std::tags::{PATIENT_ID, PATIENT_NAME};
+    #[allow(deprecated)]
+    use dicom_dictionary_std::tags::OTHER_PATIENT_I_DS;
+    use dicom_dictionary_std::tags::{
+        MODALITY, PATIENT_ID, PATIENT_NAME, SERIES_INSTANCE_UID, SERIES_NUMBER, STUDY_INSTANCE_UID,
+    };
     use dicom_dictionary_std::uids::CT_IMAGE_STORAGE;
     use dicom_object::{FileMetaTableBuilder, InMemDicomObject};
     use log::LevelFilter;
+    use std::path::Path;
+    use std::sync::atomic::Ordering;
 
     fn init_logger() {
         let _ = env_logger::builder()
@@ -439,6 +709,22 @@ mod tests {
             .try_init();
     }
 
+    /// Write a minimal DICOM file with a patient ID.
+    fn write_test_file(p: &Path, patient_id: &str) {
+        let mut obj = InMemDicomObject::new_empty();
+        obj.put_str(PATIENT_ID, VR::LO, patient_id);
+        obj.put_str(PATIENT_NAME, VR::PN, "Last^First");
+        let file_obj = obj
+            .with_meta(
+                FileMetaTableBuilder::new()
+                    .transfer_syntax(dicom_transfer_syntax_registry::default().erased().uid())
+                    .media_storage_sop_class_uid(CT_IMAGE_STORAGE),
+            )
+            .unwrap();
+        file_obj.write_to_file(p).unwrap();
+        assert!(p.is_file());
+    }
+
     #[test]
     fn dcm_cp_files() {
         init_logger();
@@ -505,6 +791,7 @@ mod tests {
                     &[idir.to_str().unwrap().to_string()],
                     odir.to_str().unwrap(),
                     &patient_id,
+                    &DcmcpOptions::default(),
                 ) {
                     Ok(_) => {
                         pt_id_match = true;
@@ -534,4 +821,431 @@ mod tests {
         std::fs::remove_dir_all(&idir).unwrap();
         std::fs::remove_dir_all(&odir).unwrap();
     }
+
+    #[test]
+    fn dcm_cp_files_stopped() {
+        init_logger();
+        let temp_dir = std::env::temp_dir();
+        let idir = temp_dir.join("rad_tools_dcm_cp_stopped").join("input");
+        let odir = temp_dir.join("rad_tools_dcm_cp_stopped").join("output");
+        if idir.is_dir() {
+            std::fs::remove_dir_all(&idir).unwrap();
+        }
+        if odir.is_dir() {
+            std::fs::remove_dir_all(&odir).unwrap();
+        }
+        std::fs::create_dir_all(&idir).unwrap();
+        std::fs::create_dir_all(&odir).unwrap();
+
+        let patient_id = "12345";
+        write_test_file(&idir.join("stopped.dcm"), patient_id);
+
+        let options = DcmcpOptions::default();
+        options.stop.store(true, Ordering::SeqCst);
+        super::dcm_cp_files(
+            &[idir.to_str().unwrap().to_string()],
+            odir.to_str().unwrap(),
+            patient_id,
+            &options,
+        )
+        .unwrap();
+        assert!(!odir.join("stopped.dcm").exists());
+
+        std::fs::remove_dir_all(&idir).unwrap();
+        std::fs::remove_dir_all(&odir).unwrap();
+    }
+
+    #[test]
+    fn dcm_cp_single_file() {
+        init_logger();
+        let temp_dir = std::env::temp_dir();
+        let idir = temp_dir
+            .join("rad_tools_dcm_cp_single_file")
+            .join("input")
+            .join("a")
+            .join("b");
+        let odir = temp_dir.join("rad_tools_dcm_cp_single_file").join("output");
+        if idir.is_dir() {
+            std::fs::remove_dir_all(&idir).unwrap();
+        }
+        if odir.is_dir() {
+            std::fs::remove_dir_all(&odir).unwrap();
+        }
+        std::fs::create_dir_all(&idir).unwrap();
+        std::fs::create_dir_all(&odir).unwrap();
+
+        let patient_id = "12345";
+        let tmp_input = idir.join("single.dcm");
+        write_test_file(&tmp_input, patient_id);
+
+        super::dcm_cp_file(
+            tmp_input.to_str().unwrap(),
+            odir.to_str().unwrap(),
+            patient_id,
+            &DcmcpOptions::default(),
+        )
+        .unwrap();
+        let tmp_output = odir.join("single.dcm");
+        assert!(tmp_output.is_file());
+        assert_eq!(1, std::fs::read_dir(&odir).unwrap().count());
+        let v1 = std::fs::read(&tmp_input).unwrap();
+        let v2 = std::fs::read(&tmp_output).unwrap();
+        assert_eq!(v1, v2);
+
+        std::fs::remove_dir_all(temp_dir.join("rad_tools_dcm_cp_single_file")).unwrap();
+    }
+
+    #[test]
+    fn patient_id_match_trim() {
+        let m = PatientIdMatch {
+            trim: true,
+            ignore_case: false,
+        };
+        assert!(m.matches("12345 ", "12345"));
+        assert!(m.matches(" 12345", "12345"));
+        assert!(!m.matches("abc", "ABC"));
+    }
+
+    #[test]
+    fn patient_id_match_ignore_case() {
+        let m = PatientIdMatch {
+            trim: true,
+            ignore_case: true,
+        };
+        assert!(m.matches("abc", "ABC"));
+        assert!(m.matches("abc ", "ABC"));
+        assert!(!m.matches("abc", "ABD"));
+    }
+
+    #[test]
+    fn patient_id_match_strict() {
+        let m = PatientIdMatch::default();
+        assert!(m.matches("12345", "12345"));
+        assert!(!m.matches("12345 ", "12345"));
+        assert!(!m.matches("abc", "ABC"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dcm_cp_files_follow_symlinks() {
+        init_logger();
+        let temp_dir = std::env::temp_dir().join("rad_tools_dcm_cp_symlinks");
+        let idir = temp_dir.join("input");
+        let odir = temp_dir.join("output");
+        let ldir = temp_dir.join("linked");
+        if temp_dir.is_dir() {
+            std::fs::remove_dir_all(&temp_dir).unwrap();
+        }
+        std::fs::create_dir_all(&idir).unwrap();
+        std::fs::create_dir_all(&odir).unwrap();
+        std::fs::create_dir_all(&ldir).unwrap();
+
+        let patient_id = "12345";
+        write_test_file(&ldir.join("linked.dcm"), patient_id);
+        std::os::unix::fs::symlink(&ldir, idir.join("linked")).unwrap();
+
+        let inputs = [idir.to_str().unwrap().to_string()];
+        super::dcm_cp_files(
+            &inputs,
+            odir.to_str().unwrap(),
+            patient_id,
+            &DcmcpOptions::default(),
+        )
+        .unwrap();
+        assert!(!odir.join("linked").join("linked.dcm").exists());
+
+        let options = DcmcpOptions {
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        super::dcm_cp_files(&inputs, odir.to_str().unwrap(), patient_id, &options).unwrap();
+        assert!(odir.join("linked").join("linked.dcm").is_file());
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn dcm_cp_file_other_patient_ids() {
+        init_logger();
+        let temp_dir = std::env::temp_dir().join("rad_tools_dcm_cp_other_ids");
+        let odir = temp_dir.join("output");
+        if temp_dir.is_dir() {
+            std::fs::remove_dir_all(&temp_dir).unwrap();
+        }
+        std::fs::create_dir_all(&odir).unwrap();
+
+        let mut obj = InMemDicomObject::new_empty();
+        obj.put_str(PATIENT_ID, VR::LO, "11111");
+        obj.put_str(PATIENT_NAME, VR::PN, "Last^First");
+        obj.put_str(OTHER_PATIENT_I_DS, VR::LO, "22222\\33333");
+        let file_obj = obj
+            .with_meta(
+                FileMetaTableBuilder::new()
+                    .transfer_syntax(dicom_transfer_syntax_registry::default().erased().uid())
+                    .media_storage_sop_class_uid(CT_IMAGE_STORAGE),
+            )
+            .unwrap();
+        let tmp_input = temp_dir.join("other_ids.dcm");
+        file_obj.write_to_file(&tmp_input).unwrap();
+
+        let input = tmp_input.to_str().unwrap();
+        let output = odir.to_str().unwrap();
+        let errs =
+            super::dcm_cp_file(input, output, "33333", &DcmcpOptions::default()).unwrap_err();
+        assert!(matches!(errs[0].as_ref(), DcmcpError::PatientIdNoMatch(_)));
+        assert!(!odir.join("other_ids.dcm").exists());
+
+        let options = DcmcpOptions {
+            also_match_other_ids: true,
+            ..Default::default()
+        };
+        super::dcm_cp_file(input, output, "33333", &options).unwrap();
+        assert!(odir.join("other_ids.dcm").is_file());
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn dcm_cp_files_limit() {
+        init_logger();
+        let temp_dir = std::env::temp_dir().join("rad_tools_dcm_cp_limit");
+        let idir = temp_dir.join("input");
+        let odir = temp_dir.join("output");
+        if temp_dir.is_dir() {
+            std::fs::remove_dir_all(&temp_dir).unwrap();
+        }
+        std::fs::create_dir_all(&idir).unwrap();
+        std::fs::create_dir_all(&odir).unwrap();
+
+        let patient_id = "12345";
+        for i in 0..5 {
+            write_test_file(&idir.join(format!("{}.dcm", i)), patient_id);
+        }
+
+        let options = DcmcpOptions {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let n = super::dcm_cp_files(
+            &[idir.to_str().unwrap().to_string()],
+            odir.to_str().unwrap(),
+            patient_id,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(2, n);
+        assert_eq!(2, std::fs::read_dir(&odir).unwrap().count());
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn dcm_cp_files_dry_run() {
+        init_logger();
+        let temp_dir = std::env::temp_dir().join("rad_tools_dcm_cp_dry_run");
+        let idir = temp_dir.join("input");
+        let odir = temp_dir.join("output");
+        if temp_dir.is_dir() {
+            std::fs::remove_dir_all(&temp_dir).unwrap();
+        }
+        std::fs::create_dir_all(idir.join("sub")).unwrap();
+        std::fs::create_dir_all(&odir).unwrap();
+
+        let patient_id = "12345";
+        write_test_file(&idir.join("a.dcm"), patient_id);
+        write_test_file(&idir.join("sub").join("b.dcm"), patient_id);
+
+        let options = DcmcpOptions {
+            dry_run: true,
+            ..Default::default()
+        };
+        let n = super::dcm_cp_files(
+            &[idir.to_str().unwrap().to_string()],
+            odir.to_str().unwrap(),
+            patient_id,
+            &options,
+        )
+        .unwrap();
+        // Both files are reported, but nothing is written to the output directory.
+        assert_eq!(2, n);
+        assert_eq!(0, std::fs::read_dir(&odir).unwrap().count());
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn dcm_cp_files_verify() {
+        init_logger();
+        let temp_dir = std::env::temp_dir().join("rad_tools_dcm_cp_verify");
+        let idir = temp_dir.join("input");
+        let odir = temp_dir.join("output");
+        if temp_dir.is_dir() {
+            std::fs::remove_dir_all(&temp_dir).unwrap();
+        }
+        std::fs::create_dir_all(&idir).unwrap();
+        std::fs::create_dir_all(&odir).unwrap();
+
+        let patient_id = "12345";
+        write_test_file(&idir.join("a.dcm"), patient_id);
+
+        let options = DcmcpOptions {
+            verify: true,
+            ..Default::default()
+        };
+        let n = super::dcm_cp_files(
+            &[idir.to_str().unwrap().to_string()],
+            odir.to_str().unwrap(),
+            patient_id,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(1, n);
+        assert_eq!(
+            std::fs::read(idir.join("a.dcm")).unwrap(),
+            std::fs::read(odir.join("a.dcm")).unwrap()
+        );
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dcm_cp_files_verify_mismatch() {
+        init_logger();
+        let temp_dir = std::env::temp_dir().join("rad_tools_dcm_cp_verify_mismatch");
+        let idir = temp_dir.join("input");
+        let odir = temp_dir.join("output");
+        if temp_dir.is_dir() {
+            std::fs::remove_dir_all(&temp_dir).unwrap();
+        }
+        std::fs::create_dir_all(idir.join("sub")).unwrap();
+        std::fs::create_dir_all(odir.join("sub")).unwrap();
+
+        let patient_id = "12345";
+        write_test_file(&idir.join("a.dcm"), patient_id);
+        write_test_file(&idir.join("sub").join("b.dcm"), patient_id);
+        // Everything written to the copy of b.dcm is discarded, so it differs from the source.
+        std::os::unix::fs::symlink("/dev/null", odir.join("sub").join("b.dcm")).unwrap();
+
+        let options = DcmcpOptions {
+            verify: true,
+            ..Default::default()
+        };
+        let errs = super::dcm_cp_files(
+            &[idir.to_str().unwrap().to_string()],
+            odir.to_str().unwrap(),
+            patient_id,
+            &options,
+        )
+        .unwrap_err();
+        assert_eq!(1, errs.len());
+        assert!(matches!(errs[0].as_ref(), DcmcpError::VerifyMismatch(_, _)));
+        assert!(odir.join("a.dcm").is_file());
+        assert!(!odir.join("sub").join("b.dcm").exists());
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn dcm_cp_files_layout_sorted() {
+        init_logger();
+        let temp_dir = std::env::temp_dir().join("rad_tools_dcm_cp_layout_sorted");
+        let idir = temp_dir.join("input");
+        let odir = temp_dir.join("output");
+        if temp_dir.is_dir() {
+            std::fs::remove_dir_all(&temp_dir).unwrap();
+        }
+        std::fs::create_dir_all(idir.join("a")).unwrap();
+        std::fs::create_dir_all(idir.join("b").join("c")).unwrap();
+        std::fs::create_dir_all(&odir).unwrap();
+
+        let patient_id = "12345";
+        let files = [
+            (idir.join("a").join("x.dcm"), "1.2.3.1", "1", "CT"),
+            (idir.join("b").join("c").join("y.dcm"), "1.2.3.1", "1", "CT"),
+            (idir.join("b").join("z.dcm"), "1.2.3.2", "2", "RTSTRUCT"),
+        ];
+        for (p, series_uid, series_nr, modality) in &files {
+            let mut obj = InMemDicomObject::new_empty();
+            obj.put_str(PATIENT_ID, VR::LO, patient_id);
+            obj.put_str(PATIENT_NAME, VR::PN, "Last^First");
+            obj.put_str(STUDY_INSTANCE_UID, VR::UI, "1.2.3");
+            obj.put_str(SERIES_INSTANCE_UID, VR::UI, *series_uid);
+            obj.put_str(SERIES_NUMBER, VR::IS, *series_nr);
+            obj.put_str(MODALITY, VR::CS, *modality);
+            let file_obj = obj
+                .with_meta(
+                    FileMetaTableBuilder::new()
+                        .transfer_syntax(dicom_transfer_syntax_registry::default().erased().uid())
+                        .media_storage_sop_class_uid(CT_IMAGE_STORAGE),
+                )
+                .unwrap();
+            file_obj.write_to_file(p).unwrap();
+        }
+
+        let options = DcmcpOptions {
+            layout: Layout::Sorted,
+            ..Default::default()
+        };
+        let n = super::dcm_cp_files(
+            &[idir.to_str().unwrap().to_string()],
+            odir.to_str().unwrap(),
+            patient_id,
+            &options,
+        )
+        .unwrap();
+        assert_eq!(3, n);
+
+        let study_dir = odir.join(patient_id).join("1.2.3");
+        let ct_dir = study_dir.join("1.2.3.1").join("1").join("CT");
+        let rtstruct_dir = study_dir.join("1.2.3.2").join("2").join("RTSTRUCT");
+        assert!(ct_dir.join("x.dcm").is_file());
+        assert!(ct_dir.join("y.dcm").is_file());
+        assert!(rtstruct_dir.join("z.dcm").is_file());
+        // The input directory tree isn't mirrored.
+        assert_eq!(1, std::fs::read_dir(&odir).unwrap().count());
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+
+    #[test]
+    fn dcm_cp_files_layout_sorted_missing_attribute() {
+        init_logger();
+        let temp_dir = std::env::temp_dir().join("rad_tools_dcm_cp_layout_sorted_missing");
+        let idir = temp_dir.join("input");
+        let odir = temp_dir.join("output");
+        if temp_dir.is_dir() {
+            std::fs::remove_dir_all(&temp_dir).unwrap();
+        }
+        std::fs::create_dir_all(idir.join("a")).unwrap();
+        std::fs::create_dir_all(&odir).unwrap();
+
+        // The test file has no study instance UID, series instance UID and modality.
+        let patient_id = "12345";
+        write_test_file(&idir.join("a").join("x.dcm"), patient_id);
+
+        let options = DcmcpOptions {
+            layout: Layout::Sorted,
+            ..Default::default()
+        };
+        let errs = super::dcm_cp_files(
+            &[idir.to_str().unwrap().to_string()],
+            odir.to_str().unwrap(),
+            patient_id,
+            &options,
+        )
+        .unwrap_err();
+        assert_eq!(1, errs.len());
+        assert!(matches!(
+            errs[0].as_ref(),
+            DcmcpError::SortData(
+                _,
+                dcm_sort::FromDicomObjectError::DicomInstanceMissingStudyInstanceUid
+            )
+        ));
+        assert_eq!(0, std::fs::read_dir(&odir).unwrap().count());
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
 }
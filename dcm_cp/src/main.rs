@@ -1,8 +1,10 @@
 use clap::Parser;
 use dicom_object::ReadError;
 use log::{error, warn};
-use rad_tools_cp_dcm::{dcm_cp_files, DcmcpError};
+use rad_tools_common::get_log_level;
+use rad_tools_cp_dcm::{dcm_cp_files, DcmcpError, DcmcpOptions, Layout, PatientIdMatch};
 use std::io::ErrorKind;
+use std::sync::atomic::Ordering;
 use tracing::{trace, Level};
 
 #[derive(Parser, Debug, Clone)]
@@ -24,6 +26,34 @@ pub struct Cli {
     /// Patient ID (unique patient identifier)
     #[arg(short, long, value_name = "PATIENT_ID")]
     patient_id: String,
+    /// Ignore leading and trailing whitespace when comparing patient IDs.
+    #[arg(long, default_value_t = false)]
+    trim: bool,
+    /// Ignore differences in case when comparing patient IDs.
+    #[arg(long, default_value_t = false)]
+    ignore_case: bool,
+    /// Also match the patient ID against the Other Patient IDs (0010,1000).
+    #[arg(long, default_value_t = false)]
+    also_match_other_ids: bool,
+    /// Stop after this number of DICOM files are copied.
+    #[arg(short, long, value_name = "N")]
+    limit: Option<usize>,
+    /// Only report the files that would be copied, without copying them.
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
+    /// Preserve the modification and access time of the copied files.
+    #[arg(long, default_value_t = false)]
+    preserve_times: bool,
+    /// Re-read each copied file and compare it with the source file.
+    #[arg(long, default_value_t = false)]
+    verify: bool,
+    /// Where the copied files are placed: mirror the input directory tree, or sort them into
+    /// <DST>/<patient ID>/<study>/<series>/<series nr>/<modality> like dcm_sort.
+    #[arg(long, value_enum, default_value_t = Layout::Mirror)]
+    layout: Layout,
+    /// Follow symbolic links while walking the input directories.
+    #[arg(long, default_value_t = false)]
+    follow_symlinks: bool,
     /// Enable logging at INFO level.
     #[arg(short, long, default_value_t = false)]
     pub verbose: bool,
@@ -37,15 +67,12 @@ pub struct Cli {
 
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
-    let level = if cli.trace {
-        Level::TRACE
-    } else if cli.debug {
-        Level::DEBUG
-    } else if cli.verbose {
+    let default_level = if cli.dry_run {
         Level::INFO
     } else {
         Level::WARN
     };
+    let level = get_log_level!(cli, default_level);
     tracing_subscriber::fmt()
         .with_thread_ids(true)
         .with_target(true)
@@ -54,8 +81,28 @@ fn main() -> anyhow::Result<()> {
 
     trace!("Commandline arguments: {:#?}", &cli);
 
+    let options = DcmcpOptions {
+        patient_id_match: PatientIdMatch {
+            trim: cli.trim,
+            ignore_case: cli.ignore_case,
+        },
+        follow_symlinks: cli.follow_symlinks,
+        also_match_other_ids: cli.also_match_other_ids,
+        limit: cli.limit,
+        dry_run: cli.dry_run,
+        preserve_times: cli.preserve_times,
+        verify: cli.verify,
+        layout: cli.layout,
+        ..Default::default()
+    };
+    let stop = options.stop.clone();
+    ctrlc::set_handler(move || {
+        warn!("Stop requested, finishing the file that's currently being processed.");
+        stop.store(true, Ordering::SeqCst);
+    })?;
+
     let mut has_errors = 0;
-    match dcm_cp_files(&cli.input, &cli.output, &cli.patient_id) {
+    match dcm_cp_files(&cli.input, &cli.output, &cli.patient_id, &options) {
         Ok(_) => {}
         Err(v) => {
             for be in v {
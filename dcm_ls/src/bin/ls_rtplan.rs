@@ -6,7 +6,8 @@ use std::path::PathBuf;
 use std::time::SystemTime;
 
 use clap::Parser;
-use tracing::{debug, error, trace, warn, Level};
+use rad_tools_common::get_log_level;
+use tracing::{debug, error, trace, warn};
 use walkdir::WalkDir;
 
 use rad_tools_dcm_ls::io::read_dicom_file_partial_by_modalities;
@@ -45,6 +46,9 @@ struct Cli {
     /// Sort the reported data by last modified timestamp of the file.
     #[arg(short, long, default_value_t = false)]
     sort: bool,
+    /// Enable logging at INFO level.
+    #[arg(short, long, default_value_t = false)]
+    verbose: bool,
     /// Enable logging at DEBUG level.
     #[arg(long, default_value_t = false)]
     debug: bool,
@@ -69,13 +73,7 @@ pub enum AppError {
 
 fn main() {
     let mut cli = Cli::parse();
-    let level = if cli.trace {
-        Level::TRACE
-    } else if cli.debug {
-        Level::DEBUG
-    } else {
-        Level::WARN
-    };
+    let level = get_log_level!(cli);
     tracing_subscriber::fmt()
         .with_thread_ids(true)
         .with_target(true)
@@ -2,24 +2,34 @@ use std::path::Path;
 
 use dicom_core::{Tag, VR};
 use dicom_dictionary_std::tags::{
-    CONTOUR_IMAGE_SEQUENCE, FRAME_OF_REFERENCE_UID, PATIENT_ID, PATIENT_NAME, PIXEL_DATA,
-    REFERENCED_FRAME_OF_REFERENCE_SEQUENCE, REFERENCED_RT_PLAN_SEQUENCE, REFERENCED_SOP_CLASS_UID,
-    REFERENCED_SOP_INSTANCE_UID, REFERENCED_STRUCTURE_SET_SEQUENCE, RT_PLAN_LABEL, RT_PLAN_NAME,
-    RT_REFERENCED_SERIES_SEQUENCE, RT_REFERENCED_STUDY_SEQUENCE, SERIES_INSTANCE_UID,
-    SOP_CLASS_UID, SOP_INSTANCE_UID,
+    BEAM_METERSET, COLUMNS, CONTOUR_IMAGE_SEQUENCE, CONTOUR_SEQUENCE, FRACTION_GROUP_NUMBER,
+    FRACTION_GROUP_SEQUENCE, FRAME_OF_REFERENCE_TRANSFORMATION_MATRIX,
+    FRAME_OF_REFERENCE_TRANSFORMATION_MATRIX_TYPE, FRAME_OF_REFERENCE_UID,
+    IMAGE_ORIENTATION_PATIENT, IMAGE_POSITION_PATIENT, MATRIX_REGISTRATION_SEQUENCE,
+    MATRIX_SEQUENCE, NUMBER_OF_FRACTIONS_PLANNED, PATIENT_ID, PATIENT_NAME, PATIENT_WEIGHT,
+    PIXEL_DATA, PIXEL_SPACING, RADIONUCLIDE_HALF_LIFE, RADIONUCLIDE_TOTAL_DOSE,
+    RADIOPHARMACEUTICAL_INFORMATION_SEQUENCE, RADIOPHARMACEUTICAL_START_TIME,
+    REFERENCED_BEAM_NUMBER, REFERENCED_BEAM_SEQUENCE, REFERENCED_FRAME_OF_REFERENCE_SEQUENCE,
+    REFERENCED_ROI_NUMBER, REFERENCED_RT_PLAN_SEQUENCE, REFERENCED_SOP_CLASS_UID,
+    REFERENCED_SOP_INSTANCE_UID, REFERENCED_STRUCTURE_SET_SEQUENCE, REGISTRATION_SEQUENCE,
+    ROI_CONTOUR_SEQUENCE, ROI_GENERATION_ALGORITHM, ROI_NAME, ROI_NUMBER, ROWS, RT_PLAN_LABEL,
+    RT_PLAN_NAME, RT_REFERENCED_SERIES_SEQUENCE, RT_REFERENCED_STUDY_SEQUENCE, SERIES_INSTANCE_UID,
+    SERIES_TIME, SOP_CLASS_UID, SOP_INSTANCE_UID, STRUCTURE_SET_ROI_SEQUENCE,
 };
 use dicom_dictionary_std::uids::{
     CT_IMAGE_STORAGE, ENHANCED_CT_IMAGE_STORAGE, MR_IMAGE_STORAGE,
     POSITRON_EMISSION_TOMOGRAPHY_IMAGE_STORAGE, RT_DOSE_STORAGE, RT_PLAN_STORAGE,
-    RT_STRUCTURE_SET_STORAGE,
+    RT_STRUCTURE_SET_STORAGE, SPATIAL_REGISTRATION_STORAGE,
 };
 use dicom_object::mem::InMemElement;
 use dicom_object::{DefaultDicomObject, InMemDicomObject, OpenFileOptions};
 use tracing::trace;
 
 use crate::model::{
-    DicomFile, FileInfo, Image, Modality, RTDose, RTPlan, RTReferencedSerie, RTReferencedStudy,
-    RTStruct, ReferencedFrameOfReference, ReferencedSopClass, SopClass,
+    DicomFile, FileInfo, FractionGroup, Image, Modality, Pet, RTDose, RTPlan, RTReferencedSerie,
+    RTReferencedStudy, RTStruct, RadiopharmaceuticalInformation, ReferencedBeam,
+    ReferencedFrameOfReference, ReferencedSopClass, Registration, RoiContour, SopClass,
+    SpatialRegistration, StructureSetRoi, Transform,
 };
 use crate::DicomError;
 use crate::DicomError::UnsupportedSOPClassUIDReader;
@@ -84,6 +94,81 @@ pub fn read_dicom_file_partial_by_modalities<P: AsRef<Path>>(
     }
 }
 
+/// Reads a PET image file with the information needed to compute standardized uptake values.
+///
+/// # Arguments
+///
+/// * `p` - The path of the PET DICOM file.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a `Pet` if successful, or a `DicomError` if an error occurred.
+/// An `UnsupportedSOPClassUIDReader` error is returned if the file isn't a PET image.
+pub fn read_pet<P: AsRef<Path>>(p: P) -> Result<Pet, DicomError> {
+    let p = p.as_ref();
+    trace!("Reading PET file: {:#?}", &p);
+    let obj = OpenFileOptions::new().read_until(PIXEL_DATA).open_file(p)?;
+    let sop = sop_class(&obj)?;
+    if sop.class_uid != POSITRON_EMISSION_TOMOGRAPHY_IMAGE_STORAGE {
+        return Err(UnsupportedSOPClassUIDReader(sop.class_uid));
+    }
+    let file_info = last_modified_time(p)?;
+
+    let patient_id = get_string(&obj, PATIENT_ID)?;
+    let series_instance_uid = get_opt_string(&obj, SERIES_INSTANCE_UID)?;
+    let patient_weight = get_opt_f64(&obj, PATIENT_WEIGHT)?;
+    let series_time = get_opt_string(&obj, SERIES_TIME)?;
+    let series_time = Some(series_time.trim().to_string()).filter(|t| !t.is_empty());
+    let radiopharmaceuticals = get_sequence(
+        obj.element_opt(RADIOPHARMACEUTICAL_INFORMATION_SEQUENCE)?,
+        radiopharmaceutical_information,
+    )?;
+    Ok(Pet {
+        file_info,
+        patient_id,
+        sop,
+        series_instance_uid,
+        patient_weight,
+        series_time,
+        radiopharmaceuticals,
+    })
+}
+
+/// Reads a Spatial Registration file with the transforms between the registered frames of reference.
+///
+/// # Arguments
+///
+/// * `p` - The path of the Spatial Registration DICOM file.
+///
+/// # Returns
+///
+/// Returns a `Result` containing a `SpatialRegistration` if successful, or a `DicomError` if an error occurred.
+/// An `UnsupportedSOPClassUIDReader` error is returned if the file isn't a Spatial Registration, and an
+/// `InvalidTransformationMatrix` error if a transformation matrix doesn't have 16 values.
+pub fn read_spatial_registration<P: AsRef<Path>>(p: P) -> Result<SpatialRegistration, DicomError> {
+    let p = p.as_ref();
+    trace!("Reading Spatial Registration file: {:#?}", &p);
+    let obj = OpenFileOptions::new().open_file(p)?;
+    let sop = sop_class(&obj)?;
+    if sop.class_uid != SPATIAL_REGISTRATION_STORAGE {
+        return Err(UnsupportedSOPClassUIDReader(sop.class_uid));
+    }
+    let file_info = last_modified_time(p)?;
+
+    let patient_id = get_string(&obj, PATIENT_ID)?;
+    let series_instance_uid = get_opt_string(&obj, SERIES_INSTANCE_UID)?;
+    let frame_of_reference_uid = get_opt_string(&obj, FRAME_OF_REFERENCE_UID)?;
+    let registrations = get_sequence(obj.element_opt(REGISTRATION_SEQUENCE)?, registration)?;
+    Ok(SpatialRegistration {
+        file_info,
+        patient_id,
+        sop,
+        series_instance_uid,
+        frame_of_reference_uid,
+        registrations,
+    })
+}
+
 /// Read a DICOM image file and create a `DicomFile` with the extracted information.
 ///
 /// # Arguments
@@ -112,6 +197,11 @@ fn read_image<P: AsRef<Path>>(path: P, obj: &DefaultDicomObject) -> Result<Dicom
     let study_instance_uid = get_string(obj, dicom_dictionary_std::tags::STUDY_INSTANCE_UID)?;
     let series_instance_uid = get_string(obj, SERIES_INSTANCE_UID)?;
     let frame_of_reference_uid = get_string(obj, FRAME_OF_REFERENCE_UID)?;
+    let image_position_patient = get_opt_f64s(obj, IMAGE_POSITION_PATIENT)?;
+    let image_orientation_patient = get_opt_f64s(obj, IMAGE_ORIENTATION_PATIENT)?;
+    let pixel_spacing = get_opt_f64s(obj, PIXEL_SPACING)?;
+    let rows = get_opt_u16(obj, ROWS)?;
+    let columns = get_opt_u16(obj, COLUMNS)?;
 
     Ok(DicomFile::Image(Image {
         file_info,
@@ -121,6 +211,11 @@ fn read_image<P: AsRef<Path>>(path: P, obj: &DefaultDicomObject) -> Result<Dicom
         study_instance_uid,
         series_instance_uid,
         frame_of_reference_uid,
+        image_position_patient,
+        image_orientation_patient,
+        pixel_spacing,
+        rows,
+        columns,
     }))
 }
 
@@ -150,6 +245,11 @@ fn read_rtstruct<P: AsRef<Path>>(
         obj.element_opt(REFERENCED_FRAME_OF_REFERENCE_SEQUENCE)?,
         referenced_frame_of_reference,
     )?;
+    let structure_set_rois = get_sequence(
+        obj.element_opt(STRUCTURE_SET_ROI_SEQUENCE)?,
+        structure_set_roi,
+    )?;
+    let roi_contours = get_sequence(obj.element_opt(ROI_CONTOUR_SEQUENCE)?, roi_contour)?;
 
     Ok(DicomFile::RTStruct(RTStruct {
         file_info,
@@ -159,6 +259,8 @@ fn read_rtstruct<P: AsRef<Path>>(
         series_instance_uid,
         label,
         referenced_frame_of_references,
+        structure_set_rois,
+        roi_contours,
     }))
 }
 
@@ -184,6 +286,7 @@ fn read_rtplan<P: AsRef<Path>>(path: P, obj: &DefaultDicomObject) -> Result<Dico
         obj.element_opt(REFERENCED_STRUCTURE_SET_SEQUENCE)?,
         referenced_sop_class,
     )?;
+    let fraction_groups = get_sequence(obj.element_opt(FRACTION_GROUP_SEQUENCE)?, fraction_group)?;
     Ok(DicomFile::RTPlan(RTPlan {
         file_info,
         patient_id,
@@ -192,6 +295,7 @@ fn read_rtplan<P: AsRef<Path>>(path: P, obj: &DefaultDicomObject) -> Result<Dico
         plan_name,
         plan_label,
         referenced_structure_sets,
+        fraction_groups,
     }))
 }
 
@@ -210,6 +314,7 @@ fn read_rtdose<P: AsRef<Path>>(path: P, obj: &DefaultDicomObject) -> Result<Dico
     let sop = sop_class(obj)?;
 
     let patient_id = get_string(obj, PATIENT_ID)?;
+    let frame_of_reference_uid = get_opt_string(obj, FRAME_OF_REFERENCE_UID)?;
     let referenced_rtplan_sequence = get_sequence(
         obj.element_opt(REFERENCED_RT_PLAN_SEQUENCE)?,
         referenced_sop_class,
@@ -218,6 +323,7 @@ fn read_rtdose<P: AsRef<Path>>(path: P, obj: &DefaultDicomObject) -> Result<Dico
         file_info,
         patient_id,
         sop,
+        frame_of_reference_uid,
         referenced_rtplan_sequence,
     }))
 }
@@ -255,6 +361,81 @@ fn get_opt_string(obj: &InMemDicomObject, tag: Tag) -> Result<String, DicomError
     }
 }
 
+/// Retrieves the value of a DICOM element as an `i32`. If the element is not found or empty, `None` is returned.
+///
+/// # Arguments
+///
+/// * `obj` - A reference to the `InMemDicomObject` from which to retrieve the element.
+/// * `tag` - The tag of the DICOM element to retrieve.
+///
+/// # Returns
+///
+/// * `Ok(Some(i32))` - If the element exists and has a valid integer value.
+/// * `Ok(None)` - If the element does not exist or has no value.
+/// * `Err(DicomError)` - If there was an error retrieving the element or converting its value to an integer.
+fn get_opt_i32(obj: &InMemDicomObject, tag: Tag) -> Result<Option<i32>, DicomError> {
+    match obj.element_opt(tag)? {
+        Some(e) if !e.to_str()?.trim().is_empty() => Ok(Some(e.to_int::<i32>()?)),
+        _ => Ok(None),
+    }
+}
+
+/// Retrieves the value of a DICOM element as an `f64`. If the element is not found or empty, `None` is returned.
+///
+/// # Arguments
+///
+/// * `obj` - A reference to the `InMemDicomObject` from which to retrieve the element.
+/// * `tag` - The tag of the DICOM element to retrieve.
+///
+/// # Returns
+///
+/// * `Ok(Some(f64))` - If the element exists and has a valid floating point value.
+/// * `Ok(None)` - If the element does not exist or has no value.
+/// * `Err(DicomError)` - If there was an error retrieving the element or converting its value to a floating point value.
+fn get_opt_f64(obj: &InMemDicomObject, tag: Tag) -> Result<Option<f64>, DicomError> {
+    match obj.element_opt(tag)? {
+        Some(e) if !e.to_str()?.trim().is_empty() => Ok(Some(e.to_float64()?)),
+        _ => Ok(None),
+    }
+}
+
+/// Retrieves the values of a DICOM element as `f64`s. If the element is not found or empty, an empty vector is returned.
+///
+/// # Arguments
+///
+/// * `obj` - A reference to the `InMemDicomObject` from which to retrieve the element.
+/// * `tag` - The tag of the DICOM element to retrieve.
+///
+/// # Returns
+///
+/// * `Ok(Vec<f64>)` - The values of the element, or an empty vector if the element does not exist or has no value.
+/// * `Err(DicomError)` - If there was an error retrieving the element or converting its values to floating point values.
+fn get_opt_f64s(obj: &InMemDicomObject, tag: Tag) -> Result<Vec<f64>, DicomError> {
+    match obj.element_opt(tag)? {
+        Some(e) if !e.to_str()?.trim().is_empty() => Ok(e.to_multi_float64()?),
+        _ => Ok(vec![]),
+    }
+}
+
+/// Retrieves the value of a DICOM element as an `u16`. If the element is not found or empty, `None` is returned.
+///
+/// # Arguments
+///
+/// * `obj` - A reference to the `InMemDicomObject` from which to retrieve the element.
+/// * `tag` - The tag of the DICOM element to retrieve.
+///
+/// # Returns
+///
+/// * `Ok(Some(u16))` - If the element exists and has a valid integer value.
+/// * `Ok(None)` - If the element does not exist or has no value.
+/// * `Err(DicomError)` - If there was an error retrieving the element or converting its value to an integer.
+fn get_opt_u16(obj: &InMemDicomObject, tag: Tag) -> Result<Option<u16>, DicomError> {
+    match obj.element_opt(tag)? {
+        Some(e) if !e.to_str()?.trim().is_empty() => Ok(Some(e.to_int::<u16>()?)),
+        _ => Ok(None),
+    }
+}
+
 /// Retrieves the last modified time of the file at the given path.
 ///
 /// # Arguments
@@ -391,6 +572,164 @@ fn rt_referenced_series(item: &InMemDicomObject) -> Result<RTReferencedSerie, Di
     })
 }
 
+/// Constructs a `FractionGroup` object from an item of the FractionGroupSequence.
+///
+/// # Arguments
+///
+/// * `item` - A reference to an `InMemDicomObject` from which the `FractionGroup` object will be created.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the `FractionGroup` object if successful, or a `DicomError` otherwise.
+fn fraction_group(item: &InMemDicomObject) -> Result<FractionGroup, DicomError> {
+    let fraction_group_number = get_opt_i32(item, FRACTION_GROUP_NUMBER)?.unwrap_or_default();
+    let number_of_fractions_planned = get_opt_i32(item, NUMBER_OF_FRACTIONS_PLANNED)?;
+    let referenced_beams =
+        get_sequence(item.element_opt(REFERENCED_BEAM_SEQUENCE)?, referenced_beam)?;
+    Ok(FractionGroup {
+        fraction_group_number,
+        number_of_fractions_planned,
+        referenced_beams,
+    })
+}
+
+/// Constructs a `ReferencedBeam` object from an item of the ReferencedBeamSequence.
+///
+/// # Arguments
+///
+/// * `item` - A reference to an `InMemDicomObject` from which the `ReferencedBeam` object will be created.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the `ReferencedBeam` object if successful, or a `DicomError` otherwise.
+fn referenced_beam(item: &InMemDicomObject) -> Result<ReferencedBeam, DicomError> {
+    let referenced_beam_number = get_opt_i32(item, REFERENCED_BEAM_NUMBER)?.unwrap_or_default();
+    let beam_meterset = get_opt_f64(item, BEAM_METERSET)?;
+    Ok(ReferencedBeam {
+        referenced_beam_number,
+        beam_meterset,
+    })
+}
+
+/// Constructs a `StructureSetRoi` object from an item of the StructureSetROISequence.
+///
+/// # Arguments
+///
+/// * `item` - A reference to an `InMemDicomObject` from which the `StructureSetRoi` object will be created.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the `StructureSetRoi` object if successful, or a `DicomError` otherwise.
+fn structure_set_roi(item: &InMemDicomObject) -> Result<StructureSetRoi, DicomError> {
+    let roi_number = get_opt_i32(item, ROI_NUMBER)?.unwrap_or_default();
+    let roi_name = get_opt_string(item, ROI_NAME)?;
+    let roi_generation_algorithm = get_opt_string(item, ROI_GENERATION_ALGORITHM)?;
+    Ok(StructureSetRoi {
+        roi_number,
+        roi_name,
+        roi_generation_algorithm,
+    })
+}
+
+/// Constructs a `RoiContour` object from an item of the ROIContourSequence.
+///
+/// Only the number of contours is kept, the contour data itself isn't read.
+///
+/// # Arguments
+///
+/// * `item` - A reference to an `InMemDicomObject` from which the `RoiContour` object will be created.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the `RoiContour` object if successful, or a `DicomError` otherwise.
+fn roi_contour(item: &InMemDicomObject) -> Result<RoiContour, DicomError> {
+    let referenced_roi_number = get_opt_i32(item, REFERENCED_ROI_NUMBER)?.unwrap_or_default();
+    let number_of_contours = item
+        .element_opt(CONTOUR_SEQUENCE)?
+        .and_then(|e| e.items())
+        .map_or(0, |items| items.len());
+    Ok(RoiContour {
+        referenced_roi_number,
+        number_of_contours,
+    })
+}
+
+/// Constructs a `RadiopharmaceuticalInformation` object from an item of the
+/// RadiopharmaceuticalInformationSequence.
+///
+/// # Arguments
+///
+/// * `item` - A reference to an `InMemDicomObject` from which the `RadiopharmaceuticalInformation` object will be created.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the `RadiopharmaceuticalInformation` object if successful, or a `DicomError` otherwise.
+fn radiopharmaceutical_information(
+    item: &InMemDicomObject,
+) -> Result<RadiopharmaceuticalInformation, DicomError> {
+    let radionuclide_total_dose = get_opt_f64(item, RADIONUCLIDE_TOTAL_DOSE)?;
+    let radionuclide_half_life = get_opt_f64(item, RADIONUCLIDE_HALF_LIFE)?;
+    let start_time = get_opt_string(item, RADIOPHARMACEUTICAL_START_TIME)?;
+    let radiopharmaceutical_start_time =
+        Some(start_time.trim().to_string()).filter(|t| !t.is_empty());
+    Ok(RadiopharmaceuticalInformation {
+        radionuclide_total_dose,
+        radionuclide_half_life,
+        radiopharmaceutical_start_time,
+    })
+}
+
+/// Constructs a `Registration` object from an item of the RegistrationSequence.
+///
+/// The transforms of all items of the MatrixRegistrationSequence are read.
+///
+/// # Arguments
+///
+/// * `item` - A reference to an `InMemDicomObject` from which the `Registration` object will be created.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the `Registration` object if successful, or a `DicomError` otherwise.
+fn registration(item: &InMemDicomObject) -> Result<Registration, DicomError> {
+    let frame_of_reference_uid = get_opt_string(item, FRAME_OF_REFERENCE_UID)?;
+    let matrix_registrations =
+        get_sequence(item.element_opt(MATRIX_REGISTRATION_SEQUENCE)?, |mr| {
+            get_sequence(mr.element_opt(MATRIX_SEQUENCE)?, transform)
+        })?;
+    Ok(Registration {
+        frame_of_reference_uid,
+        transforms: matrix_registrations.into_iter().flatten().collect(),
+    })
+}
+
+/// Constructs a `Transform` object from an item of the MatrixSequence.
+///
+/// # Arguments
+///
+/// * `item` - A reference to an `InMemDicomObject` from which the `Transform` object will be created.
+///
+/// # Returns
+///
+/// Returns a `Result` containing the `Transform` object if successful, or a `DicomError` otherwise.
+/// An `InvalidTransformationMatrix` error is returned if the matrix doesn't have 16 values.
+fn transform(item: &InMemDicomObject) -> Result<Transform, DicomError> {
+    let matrix_type = get_opt_string(item, FRAME_OF_REFERENCE_TRANSFORMATION_MATRIX_TYPE)?
+        .trim()
+        .to_string();
+    let values = get_opt_f64s(item, FRAME_OF_REFERENCE_TRANSFORMATION_MATRIX)?;
+    if values.len() != 16 {
+        return Err(DicomError::InvalidTransformationMatrix(values));
+    }
+    let mut matrix = [[0.0; 4]; 4];
+    for (row, chunk) in matrix.iter_mut().zip(values.chunks_exact(4)) {
+        row.copy_from_slice(chunk);
+    }
+    Ok(Transform {
+        matrix_type,
+        matrix,
+    })
+}
+
 /// Given a sequence and a closure `read_item`, this function retrieves the items in the sequence and applies
 /// the `read_item` closure to each item. The closure should take an `InMemDicomObject` as input and return
 /// a `Result<R, DicomError>`, where `R` is the type of the desired result.
@@ -423,3 +762,360 @@ where
     }
     Ok(v)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use dicom_core::value::DataSetSequence;
+    use dicom_core::{DataElement, PrimitiveValue, VR};
+    use dicom_dictionary_std::tags::{
+        BEAM_METERSET, COLUMNS, CONTOUR_SEQUENCE, FRACTION_GROUP_NUMBER, FRACTION_GROUP_SEQUENCE,
+        FRAME_OF_REFERENCE_TRANSFORMATION_MATRIX, FRAME_OF_REFERENCE_TRANSFORMATION_MATRIX_TYPE,
+        FRAME_OF_REFERENCE_UID, IMAGE_ORIENTATION_PATIENT, IMAGE_POSITION_PATIENT,
+        MATRIX_REGISTRATION_SEQUENCE, MATRIX_SEQUENCE, OBSERVATION_NUMBER, PATIENT_ID,
+        PATIENT_NAME, PATIENT_WEIGHT, PIXEL_SPACING, RADIONUCLIDE_HALF_LIFE,
+        RADIONUCLIDE_TOTAL_DOSE, RADIOPHARMACEUTICAL_INFORMATION_SEQUENCE,
+        RADIOPHARMACEUTICAL_START_TIME, REFERENCED_BEAM_NUMBER, REFERENCED_BEAM_SEQUENCE,
+        REFERENCED_FRAME_OF_REFERENCE_SEQUENCE, REFERENCED_ROI_NUMBER, REGISTRATION_SEQUENCE,
+        ROI_CONTOUR_SEQUENCE, ROI_GENERATION_ALGORITHM, ROI_NAME, ROI_NUMBER, ROWS,
+        RTROI_OBSERVATIONS_SEQUENCE, SERIES_INSTANCE_UID, SERIES_TIME, SOP_CLASS_UID,
+        SOP_INSTANCE_UID, STRUCTURE_SET_ROI_SEQUENCE, STUDY_INSTANCE_UID,
+    };
+    use dicom_dictionary_std::uids::{
+        CT_IMAGE_STORAGE, POSITRON_EMISSION_TOMOGRAPHY_IMAGE_STORAGE, RT_DOSE_STORAGE,
+        RT_PLAN_STORAGE, RT_STRUCTURE_SET_STORAGE, SPATIAL_REGISTRATION_STORAGE,
+    };
+    use dicom_object::mem::InMemElement;
+    use dicom_object::{FileMetaTableBuilder, InMemDicomObject};
+
+    use crate::model::{DicomFile, HasFrameOfReference, ImageGeometry};
+
+    fn referenced_beam(number: &str, meterset: Option<&str>) -> InMemDicomObject {
+        let mut item = InMemDicomObject::new_empty();
+        item.put_str(REFERENCED_BEAM_NUMBER, VR::IS, number);
+        if let Some(meterset) = meterset {
+            item.put_str(BEAM_METERSET, VR::DS, meterset);
+        }
+        item
+    }
+
+    fn sequence(tag: dicom_core::Tag, items: Vec<InMemDicomObject>) -> InMemElement {
+        DataElement::new(tag, VR::SQ, DataSetSequence::from(items))
+    }
+
+    fn structure_set_roi(number: &str, name: &str, algorithm: &str) -> InMemDicomObject {
+        let mut item = InMemDicomObject::new_empty();
+        item.put_str(ROI_NUMBER, VR::IS, number);
+        item.put_str(ROI_NAME, VR::LO, name);
+        item.put_str(ROI_GENERATION_ALGORITHM, VR::CS, algorithm);
+        item
+    }
+
+    fn rt_roi_observation(number: &str) -> InMemDicomObject {
+        let mut item = InMemDicomObject::new_empty();
+        item.put_str(OBSERVATION_NUMBER, VR::IS, number);
+        item.put_str(REFERENCED_ROI_NUMBER, VR::IS, number);
+        item
+    }
+
+    /// Write a DICOM object with the data required by all readers to a temporary file.
+    fn write_test_file(mut obj: InMemDicomObject, sop_class_uid: &str, name: &str) -> PathBuf {
+        obj.put_str(PATIENT_ID, VR::LO, "12345");
+        obj.put_str(PATIENT_NAME, VR::PN, "Last^First");
+        obj.put_str(STUDY_INSTANCE_UID, VR::UI, "1.2.3.1");
+        obj.put_str(SERIES_INSTANCE_UID, VR::UI, "1.2.3.1.1");
+        obj.put_str(SOP_CLASS_UID, VR::UI, sop_class_uid);
+        obj.put_str(SOP_INSTANCE_UID, VR::UI, "1.2.3.4");
+        let file_obj = obj
+            .with_meta(
+                FileMetaTableBuilder::new()
+                    .transfer_syntax(dicom_transfer_syntax_registry::default().erased().uid())
+                    .media_storage_sop_class_uid(sop_class_uid),
+            )
+            .unwrap();
+        let path = std::env::temp_dir().join(name);
+        file_obj.write_to_file(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn read_frame_of_reference_uid() {
+        let for_uid = "1.2.3.100";
+
+        let mut ct = InMemDicomObject::new_empty();
+        ct.put_str(FRAME_OF_REFERENCE_UID, VR::UI, for_uid);
+        let path = write_test_file(ct, CT_IMAGE_STORAGE, "rad_tools_dcm_ls_for_ct.dcm");
+        let df = super::read_dicom_file_partial(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        match df {
+            DicomFile::Image(image) => assert_eq!(Some(for_uid), image.frame_of_reference_uid()),
+            _ => panic!("Expected an Image"),
+        }
+
+        let mut rfor = InMemDicomObject::new_empty();
+        rfor.put_str(FRAME_OF_REFERENCE_UID, VR::UI, for_uid);
+        let mut rtstruct = InMemDicomObject::new_empty();
+        rtstruct.put(sequence(REFERENCED_FRAME_OF_REFERENCE_SEQUENCE, vec![rfor]));
+        let path = write_test_file(
+            rtstruct,
+            RT_STRUCTURE_SET_STORAGE,
+            "rad_tools_dcm_ls_for_rtstruct.dcm",
+        );
+        let df = super::read_dicom_file_partial(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        match df {
+            DicomFile::RTStruct(rtstruct) => {
+                assert_eq!(Some(for_uid), rtstruct.frame_of_reference_uid())
+            }
+            _ => panic!("Expected an RTStruct"),
+        }
+
+        let mut rtdose = InMemDicomObject::new_empty();
+        rtdose.put_str(FRAME_OF_REFERENCE_UID, VR::UI, for_uid);
+        let path = write_test_file(rtdose, RT_DOSE_STORAGE, "rad_tools_dcm_ls_for_rtdose.dcm");
+        let df = super::read_dicom_file_partial(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        match df {
+            DicomFile::RTDose(rtdose) => assert_eq!(Some(for_uid), rtdose.frame_of_reference_uid()),
+            _ => panic!("Expected an RTDose"),
+        }
+
+        // A dose without a Frame of Reference UID.
+        let path = write_test_file(
+            InMemDicomObject::new_empty(),
+            RT_DOSE_STORAGE,
+            "rad_tools_dcm_ls_for_rtdose_empty.dcm",
+        );
+        let df = super::read_dicom_file_partial(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        match df {
+            DicomFile::RTDose(rtdose) => assert_eq!(None, rtdose.frame_of_reference_uid()),
+            _ => panic!("Expected an RTDose"),
+        }
+    }
+
+    #[test]
+    fn read_ct_geometry() {
+        let mut ct = InMemDicomObject::new_empty();
+        ct.put_str(FRAME_OF_REFERENCE_UID, VR::UI, "1.2.3.100");
+        ct.put_str(IMAGE_POSITION_PATIENT, VR::DS, "-250\\-249.5\\10.25");
+        ct.put_str(IMAGE_ORIENTATION_PATIENT, VR::DS, "1\\0\\0\\0\\1\\0");
+        ct.put_str(PIXEL_SPACING, VR::DS, "0.976\\0.5");
+        ct.put(DataElement::new(
+            ROWS,
+            VR::US,
+            PrimitiveValue::from(512_u16),
+        ));
+        ct.put(DataElement::new(
+            COLUMNS,
+            VR::US,
+            PrimitiveValue::from(256_u16),
+        ));
+        let path = write_test_file(ct, CT_IMAGE_STORAGE, "rad_tools_dcm_ls_ct_geometry.dcm");
+        let df = super::read_dicom_file_partial(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let image = match df {
+            DicomFile::Image(image) => image,
+            _ => panic!("Expected an Image"),
+        };
+        assert_eq!(
+            ImageGeometry {
+                position: [-250.0, -249.5, 10.25],
+                orientation: [1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+                pixel_spacing: [0.976, 0.5],
+                rows: 512,
+                cols: 256,
+            },
+            image.geometry().unwrap()
+        );
+    }
+
+    #[test]
+    fn read_rtstruct_roi_summaries() {
+        let mut roi_contour = InMemDicomObject::new_empty();
+        roi_contour.put_str(REFERENCED_ROI_NUMBER, VR::IS, "1");
+        roi_contour.put(sequence(
+            CONTOUR_SEQUENCE,
+            vec![InMemDicomObject::new_empty(), InMemDicomObject::new_empty()],
+        ));
+
+        let mut obj = InMemDicomObject::new_empty();
+        obj.put(sequence(
+            STRUCTURE_SET_ROI_SEQUENCE,
+            vec![
+                structure_set_roi("1", "PTV", "MANUAL"),
+                structure_set_roi("2", "Couch", "AUTOMATIC"),
+            ],
+        ));
+        obj.put(sequence(ROI_CONTOUR_SEQUENCE, vec![roi_contour]));
+        // The second ROI has an observation, but no contour data.
+        obj.put(sequence(
+            RTROI_OBSERVATIONS_SEQUENCE,
+            vec![rt_roi_observation("1"), rt_roi_observation("2")],
+        ));
+        let path = write_test_file(
+            obj,
+            RT_STRUCTURE_SET_STORAGE,
+            "rad_tools_dcm_ls_rtstruct_roi_summaries.dcm",
+        );
+
+        let df = super::read_dicom_file_partial(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let rtstruct = match df {
+            DicomFile::RTStruct(rtstruct) => rtstruct,
+            _ => panic!("Expected an RTStruct"),
+        };
+        let summaries = rtstruct.roi_summaries();
+        assert_eq!(2, summaries.len());
+        assert_eq!(1, summaries[0].roi_number);
+        assert_eq!("PTV", summaries[0].roi_name);
+        assert_eq!("MANUAL", summaries[0].roi_generation_algorithm);
+        assert_eq!(2, summaries[0].number_of_contours);
+        assert_eq!(2, summaries[1].roi_number);
+        assert_eq!("Couch", summaries[1].roi_name);
+        assert_eq!("AUTOMATIC", summaries[1].roi_generation_algorithm);
+        assert_eq!(0, summaries[1].number_of_contours);
+    }
+
+    #[test]
+    fn read_rtplan_metersets() {
+        let mut fg1 = InMemDicomObject::new_empty();
+        fg1.put_str(FRACTION_GROUP_NUMBER, VR::IS, "1");
+        fg1.put(sequence(
+            REFERENCED_BEAM_SEQUENCE,
+            vec![
+                referenced_beam("1", Some("100.5")),
+                referenced_beam("2", Some("50.25")),
+            ],
+        ));
+        let mut fg2 = InMemDicomObject::new_empty();
+        fg2.put_str(FRACTION_GROUP_NUMBER, VR::IS, "2");
+        fg2.put(sequence(
+            REFERENCED_BEAM_SEQUENCE,
+            vec![referenced_beam("3", Some("25")), referenced_beam("4", None)],
+        ));
+
+        let mut obj = InMemDicomObject::new_empty();
+        obj.put(sequence(FRACTION_GROUP_SEQUENCE, vec![fg1, fg2]));
+        let path = write_test_file(
+            obj,
+            RT_PLAN_STORAGE,
+            "rad_tools_dcm_ls_rtplan_metersets.dcm",
+        );
+
+        let df = super::read_dicom_file_partial(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let plan = match df {
+            DicomFile::RTPlan(plan) => plan,
+            _ => panic!("Expected an RTPlan"),
+        };
+        assert_eq!(2, plan.fraction_groups.len());
+        assert_eq!(Some(100.5), plan.beam_meterset(1));
+        assert_eq!(Some(50.25), plan.beam_meterset(2));
+        assert_eq!(Some(25.0), plan.beam_meterset(3));
+        assert_eq!(None, plan.beam_meterset(4));
+        assert_eq!(None, plan.beam_meterset(5));
+        assert_eq!(150.75, plan.fraction_groups[0].total_meterset());
+        assert_eq!(175.75, plan.total_meterset());
+    }
+
+    #[test]
+    fn read_pet_suv_bw() {
+        let mut rp = InMemDicomObject::new_empty();
+        rp.put_str(RADIONUCLIDE_TOTAL_DOSE, VR::DS, "370000000");
+        rp.put_str(RADIONUCLIDE_HALF_LIFE, VR::DS, "6586.2");
+        rp.put_str(RADIOPHARMACEUTICAL_START_TIME, VR::TM, "100000");
+
+        let mut obj = InMemDicomObject::new_empty();
+        obj.put_str(PATIENT_WEIGHT, VR::DS, "70");
+        obj.put_str(SERIES_TIME, VR::TM, "110000");
+        obj.put(sequence(RADIOPHARMACEUTICAL_INFORMATION_SEQUENCE, vec![rp]));
+        let path = write_test_file(
+            obj,
+            POSITRON_EMISSION_TOMOGRAPHY_IMAGE_STORAGE,
+            "rad_tools_dcm_ls_pet_suv_bw.dcm",
+        );
+
+        let mut pet = super::read_pet(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // The injected dose decays during the hour between the injection and the series start.
+        let decayed_dose = 370e6 * 0.5f64.powf(3600.0 / 6586.2);
+        let expected = 70000.0 / decayed_dose;
+        let suv_bw = pet.suv_bw().unwrap();
+        assert!((suv_bw - expected).abs() < 1e-12);
+
+        pet.patient_weight = None;
+        assert_eq!(None, pet.suv_bw());
+    }
+
+    #[test]
+    fn read_spatial_registration() {
+        let registered = |for_uid: &str, matrix: &str| {
+            let mut m = InMemDicomObject::new_empty();
+            m.put_str(
+                FRAME_OF_REFERENCE_TRANSFORMATION_MATRIX_TYPE,
+                VR::CS,
+                "RIGID",
+            );
+            m.put_str(FRAME_OF_REFERENCE_TRANSFORMATION_MATRIX, VR::DS, matrix);
+            let mut mr = InMemDicomObject::new_empty();
+            mr.put(sequence(MATRIX_SEQUENCE, vec![m]));
+            let mut item = InMemDicomObject::new_empty();
+            item.put_str(FRAME_OF_REFERENCE_UID, VR::UI, for_uid);
+            item.put(sequence(MATRIX_REGISTRATION_SEQUENCE, vec![mr]));
+            item
+        };
+        let identity = "1\\0\\0\\0\\0\\1\\0\\0\\0\\0\\1\\0\\0\\0\\0\\1";
+        // A rotation of 90 degrees around the z-axis, followed by a translation.
+        let rotation = "0\\-1\\0\\10\\1\\0\\0\\-5.5\\0\\0\\1\\2\\0\\0\\0\\1";
+
+        let mut obj = InMemDicomObject::new_empty();
+        obj.put_str(FRAME_OF_REFERENCE_UID, VR::UI, "1.2.3.100");
+        obj.put(sequence(
+            REGISTRATION_SEQUENCE,
+            vec![
+                registered("1.2.3.100", identity),
+                registered("1.2.3.200", rotation),
+            ],
+        ));
+        let path = write_test_file(
+            obj,
+            SPATIAL_REGISTRATION_STORAGE,
+            "rad_tools_dcm_ls_spatial_registration.dcm",
+        );
+        let reg = super::read_spatial_registration(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(Some("1.2.3.100"), reg.frame_of_reference_uid());
+        assert_eq!(2, reg.registrations.len());
+        let transforms = &reg.registration("1.2.3.200").unwrap().transforms;
+        assert_eq!(1, transforms.len());
+        assert_eq!("RIGID", transforms[0].matrix_type);
+        assert_eq!(
+            [
+                [0.0, -1.0, 0.0, 10.0],
+                [1.0, 0.0, 0.0, -5.5],
+                [0.0, 0.0, 1.0, 2.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+            transforms[0].matrix
+        );
+        assert_eq!([8.0, -4.5, 5.0], transforms[0].apply([1.0, 2.0, 3.0]));
+        assert!(reg.registration("1.2.3.300").is_none());
+
+        // Not a Spatial Registration.
+        let path = write_test_file(
+            InMemDicomObject::new_empty(),
+            CT_IMAGE_STORAGE,
+            "rad_tools_dcm_ls_spatial_registration_ct.dcm",
+        );
+        let result = super::read_spatial_registration(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            result,
+            Err(crate::DicomError::UnsupportedSOPClassUIDReader(_))
+        ));
+    }
+}
@@ -16,4 +16,14 @@ pub enum DicomError {
     DicomConvertValueError(#[from] ConvertValueError),
     #[error("Unsupported SOP Class UID for reading: {0}")]
     UnsupportedSOPClassUIDReader(String),
+    #[error("Invalid Image Position (Patient): {0:?}")]
+    InvalidImagePositionPatient(Vec<f64>),
+    #[error("Invalid Image Orientation (Patient): {0:?}")]
+    InvalidImageOrientationPatient(Vec<f64>),
+    #[error("Invalid Pixel Spacing: {0:?}")]
+    InvalidPixelSpacing(Vec<f64>),
+    #[error("Invalid image size: {0:?} rows, {1:?} columns")]
+    InvalidImageSize(Option<u16>, Option<u16>),
+    #[error("Invalid Frame of Reference Transformation Matrix: {0:?}")]
+    InvalidTransformationMatrix(Vec<f64>),
 }
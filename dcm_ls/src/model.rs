@@ -6,6 +6,8 @@ use dicom_dictionary_std::uids::{
     POSITRON_EMISSION_TOMOGRAPHY_IMAGE_STORAGE,
 };
 
+use crate::DicomError;
+
 #[derive(Clone, Debug)]
 pub enum DicomFile {
     None,
@@ -31,6 +33,16 @@ pub trait HasModality {
     fn modality(&self) -> Modality;
 }
 
+pub trait HasFrameOfReference {
+    /// Get the Frame of Reference UID, `None` is returned if it isn't set.
+    fn frame_of_reference_uid(&self) -> Option<&str>;
+}
+
+/// Convert a UID into an `Option`, an empty UID is `None`.
+fn non_empty(uid: &str) -> Option<&str> {
+    Some(uid).filter(|uid| !uid.is_empty())
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Modality {
     None,
@@ -88,8 +100,16 @@ pub struct Image {
     pub study_instance_uid: String,
     pub series_instance_uid: String,
     pub frame_of_reference_uid: String,
+    pub image_position_patient: Vec<f64>,
+    pub image_orientation_patient: Vec<f64>,
+    pub pixel_spacing: Vec<f64>,
+    pub rows: Option<u16>,
+    pub columns: Option<u16>,
 }
 
+/// Tolerance used to check that the direction cosines of an image are orthonormal.
+const ORIENTATION_TOLERANCE: f64 = 1e-4;
+
 impl Image {
     pub fn is_ct(&self) -> bool {
         self.sop.class_uid == CT_IMAGE_STORAGE
@@ -106,6 +126,63 @@ impl Image {
     pub fn is_pt(&self) -> bool {
         self.sop.class_uid == POSITRON_EMISSION_TOMOGRAPHY_IMAGE_STORAGE
     }
+
+    /// Get the validated geometry of the image plane.
+    ///
+    /// An error is returned if the position, orientation or pixel spacing doesn't have the
+    /// expected number of values, if the direction cosines aren't orthonormal, if the pixel
+    /// spacing isn't positive or if the number of rows or columns is missing or zero.
+    pub fn geometry(&self) -> Result<ImageGeometry, DicomError> {
+        let position = <[f64; 3]>::try_from(self.image_position_patient.as_slice())
+            .ok()
+            .filter(|p| p.iter().all(|v| v.is_finite()))
+            .ok_or_else(|| {
+                DicomError::InvalidImagePositionPatient(self.image_position_patient.clone())
+            })?;
+        let orientation = <[f64; 6]>::try_from(self.image_orientation_patient.as_slice())
+            .ok()
+            .filter(is_orthonormal)
+            .ok_or_else(|| {
+                DicomError::InvalidImageOrientationPatient(self.image_orientation_patient.clone())
+            })?;
+        let pixel_spacing = <[f64; 2]>::try_from(self.pixel_spacing.as_slice())
+            .ok()
+            .filter(|ps| ps.iter().all(|v| v.is_finite() && *v > 0.0))
+            .ok_or_else(|| DicomError::InvalidPixelSpacing(self.pixel_spacing.clone()))?;
+        let (rows, cols) = match (self.rows, self.columns) {
+            (Some(rows), Some(cols)) if rows > 0 && cols > 0 => (rows, cols),
+            _ => return Err(DicomError::InvalidImageSize(self.rows, self.columns)),
+        };
+        Ok(ImageGeometry {
+            position,
+            orientation,
+            pixel_spacing,
+            rows,
+            cols,
+        })
+    }
+}
+
+/// Check if the row and column direction cosines are unit vectors and perpendicular.
+fn is_orthonormal(orientation: &[f64; 6]) -> bool {
+    let (row, col) = orientation.split_at(3);
+    let dot = |a: &[f64], b: &[f64]| a.iter().zip(b).map(|(x, y)| x * y).sum::<f64>();
+    (dot(row, row) - 1.0).abs() < ORIENTATION_TOLERANCE
+        && (dot(col, col) - 1.0).abs() < ORIENTATION_TOLERANCE
+        && dot(row, col).abs() < ORIENTATION_TOLERANCE
+}
+
+/// Geometry of an image plane in the patient coordinate system.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ImageGeometry {
+    /// Position (mm) of the center of the first pixel.
+    pub position: [f64; 3],
+    /// Direction cosines of the first row and the first column.
+    pub orientation: [f64; 6],
+    /// Distance (mm) between the centers of adjacent rows and of adjacent columns.
+    pub pixel_spacing: [f64; 2],
+    pub rows: u16,
+    pub cols: u16,
 }
 
 impl HasModality for Image {
@@ -124,6 +201,12 @@ impl HasModality for Image {
     }
 }
 
+impl HasFrameOfReference for Image {
+    fn frame_of_reference_uid(&self) -> Option<&str> {
+        non_empty(&self.frame_of_reference_uid)
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct RTStruct {
     pub file_info: FileInfo,
@@ -133,6 +216,61 @@ pub struct RTStruct {
     pub series_instance_uid: String,
     pub label: String,
     pub referenced_frame_of_references: Vec<ReferencedFrameOfReference>,
+    pub structure_set_rois: Vec<StructureSetRoi>,
+    pub roi_contours: Vec<RoiContour>,
+}
+
+impl RTStruct {
+    /// Get a summary of each ROI in the structure set.
+    ///
+    /// The ROIs are listed in the order of the StructureSetROISequence.
+    /// ROIs without contour data (e.g. ROIs that only have observations) have zero contours.
+    pub fn roi_summaries(&self) -> Vec<RoiSummary> {
+        self.structure_set_rois
+            .iter()
+            .map(|roi| RoiSummary {
+                roi_number: roi.roi_number,
+                roi_name: roi.roi_name.clone(),
+                roi_generation_algorithm: roi.roi_generation_algorithm.clone(),
+                number_of_contours: self
+                    .roi_contours
+                    .iter()
+                    .filter(|rc| rc.referenced_roi_number == roi.roi_number)
+                    .map(|rc| rc.number_of_contours)
+                    .sum(),
+            })
+            .collect()
+    }
+}
+
+impl HasFrameOfReference for RTStruct {
+    /// Get the first Frame of Reference UID of the ReferencedFrameOfReferenceSequence.
+    fn frame_of_reference_uid(&self) -> Option<&str> {
+        self.referenced_frame_of_references
+            .iter()
+            .find_map(|rfor| non_empty(&rfor.frame_of_reference_uid))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct StructureSetRoi {
+    pub roi_number: i32,
+    pub roi_name: String,
+    pub roi_generation_algorithm: String,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RoiContour {
+    pub referenced_roi_number: i32,
+    pub number_of_contours: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RoiSummary {
+    pub roi_number: i32,
+    pub roi_name: String,
+    pub roi_generation_algorithm: String,
+    pub number_of_contours: usize,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -144,6 +282,31 @@ pub struct RTPlan {
     pub plan_name: String,
     pub plan_label: String,
     pub referenced_structure_sets: Vec<ReferencedSopClass>,
+    pub fraction_groups: Vec<FractionGroup>,
+}
+
+impl RTPlan {
+    /// Get the meterset (per fraction) of a beam.
+    ///
+    /// The meterset is read from the first fraction group that references the beam.
+    /// `None` is returned if no fraction group references the beam, or if the meterset isn't set.
+    pub fn beam_meterset(&self, beam_number: i32) -> Option<f64> {
+        self.fraction_groups
+            .iter()
+            .flat_map(|fg| fg.referenced_beams.iter())
+            .find(|rb| rb.referenced_beam_number == beam_number)
+            .and_then(|rb| rb.beam_meterset)
+    }
+
+    /// Get the total meterset (per fraction) of all beams in all fraction groups.
+    ///
+    /// Beams without a meterset don't contribute to the total.
+    pub fn total_meterset(&self) -> f64 {
+        self.fraction_groups
+            .iter()
+            .map(|fg| fg.total_meterset())
+            .sum()
+    }
 }
 
 impl HasModality for RTPlan {
@@ -152,11 +315,37 @@ impl HasModality for RTPlan {
     }
 }
 
+#[derive(Clone, Debug, Default)]
+pub struct FractionGroup {
+    pub fraction_group_number: i32,
+    pub number_of_fractions_planned: Option<i32>,
+    pub referenced_beams: Vec<ReferencedBeam>,
+}
+
+impl FractionGroup {
+    /// Get the total meterset (per fraction) of the beams in the fraction group.
+    ///
+    /// Beams without a meterset don't contribute to the total.
+    pub fn total_meterset(&self) -> f64 {
+        self.referenced_beams
+            .iter()
+            .filter_map(|rb| rb.beam_meterset)
+            .sum()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ReferencedBeam {
+    pub referenced_beam_number: i32,
+    pub beam_meterset: Option<f64>,
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct RTDose {
     pub file_info: FileInfo,
     pub patient_id: String,
     pub sop: SopClass,
+    pub frame_of_reference_uid: String,
     pub referenced_rtplan_sequence: Vec<ReferencedSopClass>,
 }
 
@@ -165,3 +354,202 @@ impl HasModality for RTDose {
         Modality::RtDose
     }
 }
+
+impl HasFrameOfReference for RTDose {
+    fn frame_of_reference_uid(&self) -> Option<&str> {
+        non_empty(&self.frame_of_reference_uid)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Pet {
+    pub file_info: FileInfo,
+    pub patient_id: String,
+    pub sop: SopClass,
+    pub series_instance_uid: String,
+    /// Patient weight in kg.
+    pub patient_weight: Option<f64>,
+    /// Series time (HHMMSS.FFFFFF), used as the reference time of the decay correction.
+    pub series_time: Option<String>,
+    pub radiopharmaceuticals: Vec<RadiopharmaceuticalInformation>,
+}
+
+impl Pet {
+    /// Get the factor that converts the activity concentration (Bq/ml) into the body weight
+    /// standardized uptake value (SUVbw).
+    ///
+    /// The injected dose of the first radiopharmaceutical is decay corrected from the injection
+    /// time to the series time.
+    /// `None` is returned if the patient weight, injected dose, half-life, injection time or series
+    /// time isn't available.
+    pub fn suv_bw(&self) -> Option<f64> {
+        let weight = self.patient_weight.filter(|w| *w > 0.0)?;
+        let rp = self.radiopharmaceuticals.first()?;
+        let dose = rp.radionuclide_total_dose.filter(|d| *d > 0.0)?;
+        let half_life = rp.radionuclide_half_life.filter(|t| *t > 0.0)?;
+        let start = seconds_since_midnight(rp.radiopharmaceutical_start_time.as_deref()?)?;
+        let series = seconds_since_midnight(self.series_time.as_deref()?)?;
+        let mut elapsed = series - start;
+        if elapsed < 0.0 {
+            // The acquisition started after midnight.
+            elapsed += 86400.0;
+        }
+        let decayed_dose = dose * (-elapsed * std::f64::consts::LN_2 / half_life).exp();
+        Some(weight * 1000.0 / decayed_dose)
+    }
+}
+
+impl HasModality for Pet {
+    fn modality(&self) -> Modality {
+        Modality::Pt
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RadiopharmaceuticalInformation {
+    /// Injected dose in Bq.
+    pub radionuclide_total_dose: Option<f64>,
+    /// Half-life of the radionuclide in seconds.
+    pub radionuclide_half_life: Option<f64>,
+    /// Injection time (HHMMSS.FFFFFF).
+    pub radiopharmaceutical_start_time: Option<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SpatialRegistration {
+    pub file_info: FileInfo,
+    pub patient_id: String,
+    pub sop: SopClass,
+    pub series_instance_uid: String,
+    /// Frame of Reference UID to which the registered frames of reference are transformed.
+    pub frame_of_reference_uid: String,
+    pub registrations: Vec<Registration>,
+}
+
+impl SpatialRegistration {
+    /// Get the registration of a frame of reference.
+    ///
+    /// `None` is returned if the frame of reference isn't registered.
+    pub fn registration(&self, frame_of_reference_uid: &str) -> Option<&Registration> {
+        self.registrations
+            .iter()
+            .find(|r| r.frame_of_reference_uid == frame_of_reference_uid)
+    }
+}
+
+impl HasFrameOfReference for SpatialRegistration {
+    fn frame_of_reference_uid(&self) -> Option<&str> {
+        non_empty(&self.frame_of_reference_uid)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Registration {
+    /// Frame of Reference UID of the registered data.
+    pub frame_of_reference_uid: String,
+    /// Transforms of the MatrixSequence, in the order of the sequence.
+    pub transforms: Vec<Transform>,
+}
+
+/// Rigid or affine transform from a frame of reference to another frame of reference.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transform {
+    /// Frame of Reference Transformation Matrix Type (e.g. RIGID).
+    pub matrix_type: String,
+    /// 4x4 transformation matrix, in row major order.
+    pub matrix: [[f64; 4]; 4],
+}
+
+impl Transform {
+    /// Transform a point (mm) in homogeneous coordinates.
+    pub fn apply(&self, point: [f64; 3]) -> [f64; 3] {
+        let m = &self.matrix;
+        let mut out = [0.0; 3];
+        for (i, v) in out.iter_mut().enumerate() {
+            *v = m[i][0] * point[0] + m[i][1] * point[1] + m[i][2] * point[2] + m[i][3];
+        }
+        out
+    }
+}
+
+/// Convert a DICOM time (TM) value into the number of seconds since midnight.
+///
+/// Minutes and seconds are optional, as allowed by the DICOM standard.
+/// `None` is returned if the value isn't a valid time.
+fn seconds_since_midnight(tm: &str) -> Option<f64> {
+    let tm = tm.trim();
+    let (hms, fraction) = match tm.split_once('.') {
+        Some((hms, fraction)) => (hms, fraction),
+        None => (tm, ""),
+    };
+    if !(2..=6).contains(&hms.len())
+        || !hms.len().is_multiple_of(2)
+        || !hms.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let field = |i: usize| {
+        hms.get(i..i + 2)
+            .map_or(Some(0.0), |v| v.parse::<f64>().ok())
+    };
+    let mut seconds = field(0)? * 3600.0 + field(2)? * 60.0 + field(4)?;
+    if !fraction.is_empty() {
+        seconds += format!("0.{}", fraction).parse::<f64>().ok()?;
+    }
+    Some(seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{seconds_since_midnight, Image};
+    use crate::DicomError;
+
+    #[test]
+    fn seconds_since_midnight_formats() {
+        assert_eq!(Some(36000.0), seconds_since_midnight("10"));
+        assert_eq!(Some(37800.0), seconds_since_midnight("1030"));
+        assert_eq!(Some(37815.5), seconds_since_midnight("103015.5"));
+        assert_eq!(None, seconds_since_midnight("10:30"));
+        assert_eq!(None, seconds_since_midnight(""));
+    }
+
+    #[test]
+    fn image_geometry_invalid() {
+        let image = Image {
+            image_position_patient: vec![-250.0, -250.0, 10.0],
+            image_orientation_patient: vec![1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            pixel_spacing: vec![0.5, 0.5],
+            rows: Some(512),
+            columns: Some(512),
+            ..Default::default()
+        };
+        assert!(image.geometry().is_ok());
+
+        let not_perpendicular = Image {
+            image_orientation_patient: vec![1.0, 0.0, 0.0, 1.0, 0.0, 0.0],
+            ..image.clone()
+        };
+        assert!(matches!(
+            not_perpendicular.geometry(),
+            Err(DicomError::InvalidImageOrientationPatient(_))
+        ));
+
+        let missing_spacing = Image {
+            pixel_spacing: vec![0.5],
+            ..image.clone()
+        };
+        assert!(matches!(
+            missing_spacing.geometry(),
+            Err(DicomError::InvalidPixelSpacing(_))
+        ));
+
+        let no_rows = Image {
+            rows: None,
+            ..image
+        };
+        assert!(matches!(
+            no_rows.geometry(),
+            Err(DicomError::InvalidImageSize(None, Some(512)))
+        ));
+    }
+}
@@ -0,0 +1,492 @@
+//! Creation of a DICOMDIR index for a directory of sorted DICOM files.
+//!
+//! The directory records are built as DICOM objects and written with `dicom-object` in Explicit VR
+//! Little Endian. The byte offsets between the records are computed from the encoded length of
+//! each record before the DICOMDIR is written.
+//! Referenced file IDs are the relative paths of the DICOM files, the component restrictions of
+//! strict media profiles (8 characters, uppercase) aren't enforced.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use dicom_core::value::DataSetSequence;
+use dicom_core::{DataElement, PrimitiveValue, Tag, VR};
+use dicom_dictionary_std::tags::{
+    ACCESSION_NUMBER, DIRECTORY_RECORD_SEQUENCE, DIRECTORY_RECORD_TYPE, FILE_SET_CONSISTENCY_FLAG,
+    FILE_SET_ID, INSTANCE_NUMBER, MODALITY, OFFSET_OF_REFERENCED_LOWER_LEVEL_DIRECTORY_ENTITY,
+    OFFSET_OF_THE_FIRST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+    OFFSET_OF_THE_LAST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+    OFFSET_OF_THE_NEXT_DIRECTORY_RECORD, PATIENT_ID, PATIENT_NAME, PIXEL_DATA, RECORD_IN_USE_FLAG,
+    REFERENCED_FILE_ID, REFERENCED_SOP_CLASS_UID_IN_FILE, REFERENCED_SOP_INSTANCE_UID_IN_FILE,
+    REFERENCED_TRANSFER_SYNTAX_UID_IN_FILE, ROWS, SERIES_DESCRIPTION, SERIES_INSTANCE_UID,
+    SERIES_NUMBER, STUDY_DATE, STUDY_DESCRIPTION, STUDY_ID, STUDY_INSTANCE_UID, STUDY_TIME,
+};
+use dicom_dictionary_std::uids::{EXPLICIT_VR_LITTLE_ENDIAN, MEDIA_STORAGE_DIRECTORY_STORAGE};
+use dicom_encoding::TransferSyntax;
+use dicom_object::{
+    FileDicomObject, FileMetaTableBuilder, InMemDicomObject, OpenFileOptions, WriteError,
+};
+use tracing::{debug, trace};
+use walkdir::WalkDir;
+
+use crate::support::get_str_or_default;
+use crate::{Error, Result};
+
+/// Filename of the DICOMDIR.
+pub const DICOMDIR: &str = "DICOMDIR";
+
+/// Encoded size of the sequence delimitation item, which ends the directory record sequence.
+///
+/// The directory record sequence is written with an undefined length.
+const SEQUENCE_DELIMITATION_LEN: usize = 8;
+
+/// Directory record and the records at the lower directory level.
+struct Record {
+    obj: InMemDicomObject,
+    children: Vec<Record>,
+}
+
+/// Directory record after flattening the record tree.
+struct FlatRecord {
+    obj: InMemDicomObject,
+    next: Option<usize>,
+    lower: Option<usize>,
+}
+
+#[derive(Default)]
+struct Instance {
+    file_id: Vec<String>,
+    record_type: &'static str,
+    sop_class_uid: String,
+    sop_instance_uid: String,
+    transfer_syntax_uid: String,
+    instance_number: String,
+}
+
+#[derive(Default)]
+struct Series {
+    modality: String,
+    series_number: String,
+    series_descr: String,
+    instances: Vec<Instance>,
+}
+
+#[derive(Default)]
+struct Study {
+    study_date: String,
+    study_time: String,
+    study_descr: String,
+    study_id: String,
+    accession_number: String,
+    series: BTreeMap<String, Series>,
+}
+
+#[derive(Default)]
+struct Patient {
+    patient_name: String,
+    studies: BTreeMap<String, Study>,
+}
+
+/// Write a DICOMDIR in a directory, referencing all DICOM files in that directory (recursively).
+///
+/// An existing DICOMDIR is replaced. Files that can't be read as DICOM data, and DICOM files for
+/// which no directory record type is known, are skipped.
+///
+/// # Arguments
+///
+/// * `dir`: root directory of the file-set
+///
+/// returns: Result<usize>
+/// The number of instances referenced by the DICOMDIR is returned, otherwise an error.
+pub fn write_dicomdir<P>(dir: P) -> Result<usize>
+where
+    P: AsRef<Path>,
+{
+    let dir = dir.as_ref();
+    let path = dir.join(DICOMDIR);
+    let (patients, ninstances) = read_patients(dir)?;
+    let mut records = flatten(patient_records(patients));
+
+    let mut file_obj = root_dataset(vec![])
+        .with_meta(
+            FileMetaTableBuilder::new()
+                .transfer_syntax(EXPLICIT_VR_LITTLE_ENDIAN)
+                .media_storage_sop_class_uid(MEDIA_STORAGE_DIRECTORY_STORAGE)
+                .media_storage_sop_instance_uid(generate_uid()),
+        )
+        .map_err(dicomdir_error(&path))?;
+    let offsets = record_offsets(&file_obj, &records).map_err(dicomdir_error(&path))?;
+    let offset_of = |index: Option<usize>| index.map(|i| offsets[i]).unwrap_or_default();
+    let last_root = records.first().map(|_| {
+        let mut index = 0;
+        while let Some(next) = records[index].next {
+            index = next;
+        }
+        index
+    });
+
+    for record in &mut records {
+        put_offset(
+            &mut record.obj,
+            OFFSET_OF_THE_NEXT_DIRECTORY_RECORD,
+            offset_of(record.next),
+        );
+        put_offset(
+            &mut record.obj,
+            OFFSET_OF_REFERENCED_LOWER_LEVEL_DIRECTORY_ENTITY,
+            offset_of(record.lower),
+        );
+    }
+    put_offset(
+        &mut file_obj,
+        OFFSET_OF_THE_FIRST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+        offset_of(records.first().map(|_| 0)),
+    );
+    put_offset(
+        &mut file_obj,
+        OFFSET_OF_THE_LAST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+        offset_of(last_root),
+    );
+    file_obj.put(record_sequence(
+        records.into_iter().map(|r| r.obj).collect(),
+    ));
+
+    debug!("Writing DICOMDIR [{} instances]: {:#?}", ninstances, &path);
+    file_obj
+        .write_to_file(&path)
+        .map_err(dicomdir_error(&path))?;
+    Ok(ninstances)
+}
+
+/// Convert an error of `dicom-object` that occurred while creating the DICOMDIR.
+fn dicomdir_error<E>(path: &Path) -> impl Fn(E) -> Error + '_
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    move |e| Error::DicomDir(path.to_path_buf(), std::io::Error::other(e))
+}
+
+/// Compute the byte offset of each directory record from the start of the DICOMDIR file.
+///
+/// # Arguments
+///
+/// * `file_obj`: DICOMDIR with an empty directory record sequence
+/// * `records`: directory records in the order they are stored in the DICOMDIR
+///
+/// The offsets are computed with placeholder values for the offsets in the records. Offsets are
+/// encoded with a fixed size, so filling them in doesn't change the encoded length of a record.
+fn record_offsets(
+    file_obj: &FileDicomObject<InMemDicomObject>,
+    records: &[FlatRecord],
+) -> std::result::Result<Vec<u32>, Box<WriteError>> {
+    let ts = dicom_transfer_syntax_registry::entries::EXPLICIT_VR_LITTLE_ENDIAN.erased();
+    let mut buf = vec![];
+    file_obj.write_all(&mut buf)?;
+    let mut position = buf.len() - SEQUENCE_DELIMITATION_LEN;
+    let item_overhead = dataset_len(&root_dataset(vec![InMemDicomObject::new_empty()]), &ts)?
+        - dataset_len(&root_dataset(vec![]), &ts)?;
+    let mut offsets = Vec::with_capacity(records.len());
+    for record in records {
+        offsets.push(position as u32);
+        position += item_overhead + dataset_len(&record.obj, &ts)?;
+    }
+    Ok(offsets)
+}
+
+/// Read the DICOM files in a directory and group them by patient, study and series.
+fn read_patients(dir: &Path) -> Result<(BTreeMap<String, Patient>, usize)> {
+    let mut patients: BTreeMap<String, Patient> = BTreeMap::new();
+    let mut ninstances = 0;
+    for entry in WalkDir::new(dir) {
+        let entry = entry?;
+        let path = entry.path();
+        if !path.is_file() || path.file_name().is_some_and(|f| f == DICOMDIR) {
+            continue;
+        }
+        let obj = match OpenFileOptions::new()
+            .read_until(PIXEL_DATA)
+            .open_file(path)
+        {
+            Ok(obj) => obj,
+            Err(e) => {
+                debug!("Not adding {:#?} to the DICOMDIR: {:#?}", path, e);
+                continue;
+            }
+        };
+        let modality = get_str_or_default(&obj, MODALITY);
+        let record_type =
+            match record_type(&modality, obj.element_opt(ROWS).ok().flatten().is_some()) {
+                Some(record_type) => record_type,
+                None => {
+                    debug!(
+                        "Not adding {:#?} to the DICOMDIR: unknown record type",
+                        path
+                    );
+                    continue;
+                }
+            };
+        let rel_path = path.strip_prefix(dir).unwrap_or(path);
+        trace!("Adding {:#?} to the DICOMDIR", rel_path);
+        let meta = obj.meta();
+        let instance = Instance {
+            file_id: rel_path
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect(),
+            record_type,
+            sop_class_uid: meta.media_storage_sop_class_uid().to_string(),
+            sop_instance_uid: meta.media_storage_sop_instance_uid().to_string(),
+            transfer_syntax_uid: meta.transfer_syntax().to_string(),
+            instance_number: get_str_or_default(&obj, INSTANCE_NUMBER),
+        };
+
+        let patient = patients
+            .entry(get_str_or_default(&obj, PATIENT_ID))
+            .or_insert_with(|| Patient {
+                patient_name: get_str_or_default(&obj, PATIENT_NAME),
+                ..Default::default()
+            });
+        let study = get_or_insert_study(patient, &obj);
+        let series = study
+            .series
+            .entry(get_str_or_default(&obj, SERIES_INSTANCE_UID))
+            .or_insert_with(|| Series {
+                modality,
+                series_number: get_str_or_default(&obj, SERIES_NUMBER),
+                series_descr: get_str_or_default(&obj, SERIES_DESCRIPTION),
+                ..Default::default()
+            });
+        series.instances.push(instance);
+        ninstances += 1;
+    }
+    Ok((patients, ninstances))
+}
+
+/// Get the study of a patient to which a DICOM object belongs, the study is added if needed.
+fn get_or_insert_study<'a>(patient: &'a mut Patient, obj: &InMemDicomObject) -> &'a mut Study {
+    patient
+        .studies
+        .entry(get_str_or_default(obj, STUDY_INSTANCE_UID))
+        .or_insert_with(|| Study {
+            study_date: get_str_or_default(obj, STUDY_DATE),
+            study_time: get_str_or_default(obj, STUDY_TIME),
+            study_descr: get_str_or_default(obj, STUDY_DESCRIPTION),
+            study_id: get_str_or_default(obj, STUDY_ID),
+            accession_number: get_str_or_default(obj, ACCESSION_NUMBER),
+            ..Default::default()
+        })
+}
+
+/// Get the directory record type of an instance.
+///
+/// # Arguments
+///
+/// * `modality`: modality of the instance
+/// * `has_rows`: the instance has the Rows (0028,0010) attribute of an image
+///
+/// returns: Option<&'static str>
+/// The record type, or `None` if the instance isn't an image and its modality is unknown.
+fn record_type(modality: &str, has_rows: bool) -> Option<&'static str> {
+    match modality {
+        "RTSTRUCT" => Some("RT STRUCTURE"),
+        "RTPLAN" => Some("RT PLAN"),
+        "RTDOSE" => Some("RT DOSE"),
+        "RTRECORD" => Some("RT TREAT RECORD"),
+        "SR" => Some("SR DOCUMENT"),
+        "KO" => Some("KEY OBJECT DOC"),
+        "PR" => Some("PRESENTATION"),
+        "REG" => Some("REGISTRATION"),
+        "CT" | "MR" | "PT" | "NM" | "US" | "CR" | "DX" | "MG" | "XA" | "RF" | "RTIMAGE" | "OT" => {
+            Some("IMAGE")
+        }
+        _ if has_rows => Some("IMAGE"),
+        _ => None,
+    }
+}
+
+/// Create a directory record with the elements that are set once the offsets are known.
+fn record(record_type: &str) -> InMemDicomObject {
+    let mut obj = InMemDicomObject::new_empty();
+    put_offset(&mut obj, OFFSET_OF_THE_NEXT_DIRECTORY_RECORD, 0);
+    obj.put(DataElement::new(
+        RECORD_IN_USE_FLAG,
+        VR::US,
+        PrimitiveValue::from(0xFFFFu16),
+    ));
+    put_offset(
+        &mut obj,
+        OFFSET_OF_REFERENCED_LOWER_LEVEL_DIRECTORY_ENTITY,
+        0,
+    );
+    obj.put_str(DIRECTORY_RECORD_TYPE, VR::CS, record_type);
+    obj
+}
+
+/// Create the tree of directory records.
+fn patient_records(patients: BTreeMap<String, Patient>) -> Vec<Record> {
+    patients
+        .into_iter()
+        .map(|(patient_id, patient)| {
+            let mut obj = record("PATIENT");
+            obj.put_str(PATIENT_NAME, VR::PN, patient.patient_name);
+            obj.put_str(PATIENT_ID, VR::LO, patient_id);
+            Record {
+                obj,
+                children: patient
+                    .studies
+                    .into_iter()
+                    .map(|(study_uid, study)| study_record(study_uid, study))
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Create the directory record of a study and its series.
+fn study_record(study_uid: String, study: Study) -> Record {
+    let mut obj = record("STUDY");
+    obj.put_str(STUDY_DATE, VR::DA, study.study_date);
+    obj.put_str(STUDY_TIME, VR::TM, study.study_time);
+    obj.put_str(ACCESSION_NUMBER, VR::SH, study.accession_number);
+    obj.put_str(STUDY_DESCRIPTION, VR::LO, study.study_descr);
+    obj.put_str(STUDY_INSTANCE_UID, VR::UI, study_uid);
+    obj.put_str(STUDY_ID, VR::SH, study.study_id);
+    Record {
+        obj,
+        children: study
+            .series
+            .into_iter()
+            .map(|(series_uid, series)| series_record(series_uid, series))
+            .collect(),
+    }
+}
+
+/// Create the directory record of a series and its instances.
+fn series_record(series_uid: String, series: Series) -> Record {
+    let mut obj = record("SERIES");
+    obj.put_str(MODALITY, VR::CS, series.modality);
+    obj.put_str(SERIES_DESCRIPTION, VR::LO, series.series_descr);
+    obj.put_str(SERIES_INSTANCE_UID, VR::UI, series_uid);
+    obj.put_str(SERIES_NUMBER, VR::IS, series.series_number);
+    Record {
+        obj,
+        children: series
+            .instances
+            .into_iter()
+            .map(|instance| {
+                let mut obj = record(instance.record_type);
+                obj.put_str(REFERENCED_FILE_ID, VR::CS, instance.file_id.join("\\"));
+                obj.put_str(
+                    REFERENCED_SOP_CLASS_UID_IN_FILE,
+                    VR::UI,
+                    instance.sop_class_uid,
+                );
+                obj.put_str(
+                    REFERENCED_SOP_INSTANCE_UID_IN_FILE,
+                    VR::UI,
+                    instance.sop_instance_uid,
+                );
+                obj.put_str(
+                    REFERENCED_TRANSFER_SYNTAX_UID_IN_FILE,
+                    VR::UI,
+                    instance.transfer_syntax_uid,
+                );
+                obj.put_str(INSTANCE_NUMBER, VR::IS, instance.instance_number);
+                Record {
+                    obj,
+                    children: vec![],
+                }
+            })
+            .collect(),
+    }
+}
+
+/// Flatten a tree of directory records in the order they are stored in the DICOMDIR.
+fn flatten(records: Vec<Record>) -> Vec<FlatRecord> {
+    let mut flat = vec![];
+    flatten_into(records, &mut flat);
+    flat
+}
+
+/// Flatten the records of a directory entity into `flat`.
+///
+/// Returns the index of the first record of the directory entity.
+fn flatten_into(records: Vec<Record>, flat: &mut Vec<FlatRecord>) -> Option<usize> {
+    let first = flat.len();
+    let mut previous: Option<usize> = None;
+    for record in records {
+        let index = flat.len();
+        if let Some(previous) = previous {
+            flat[previous].next = Some(index);
+        }
+        flat.push(FlatRecord {
+            obj: record.obj,
+            next: None,
+            lower: None,
+        });
+        flat[index].lower = flatten_into(record.children, flat);
+        previous = Some(index);
+    }
+    if flat.len() > first {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+/// Create the data set of the DICOMDIR with the directory records.
+fn root_dataset(records: Vec<InMemDicomObject>) -> InMemDicomObject {
+    let mut obj = InMemDicomObject::new_empty();
+    obj.put_str(FILE_SET_ID, VR::CS, "");
+    put_offset(
+        &mut obj,
+        OFFSET_OF_THE_FIRST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+        0,
+    );
+    put_offset(
+        &mut obj,
+        OFFSET_OF_THE_LAST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+        0,
+    );
+    obj.put(DataElement::new(
+        FILE_SET_CONSISTENCY_FLAG,
+        VR::US,
+        PrimitiveValue::from(0u16),
+    ));
+    obj.put(record_sequence(records));
+    obj
+}
+
+/// Create the directory record sequence.
+fn record_sequence(records: Vec<InMemDicomObject>) -> DataElement<InMemDicomObject> {
+    DataElement::new(
+        DIRECTORY_RECORD_SEQUENCE,
+        VR::SQ,
+        DataSetSequence::from(records),
+    )
+}
+
+/// Set an offset (UL) in a directory record or in the DICOMDIR data set.
+fn put_offset(obj: &mut InMemDicomObject, tag: Tag, offset: u32) {
+    obj.put(DataElement::new(tag, VR::UL, PrimitiveValue::from(offset)));
+}
+
+/// Encoded length of a data set.
+fn dataset_len(
+    obj: &InMemDicomObject,
+    ts: &TransferSyntax,
+) -> std::result::Result<usize, Box<WriteError>> {
+    let mut buf = vec![];
+    obj.write_dataset_with_ts(&mut buf, ts)?;
+    Ok(buf.len())
+}
+
+/// Generate a UID for the DICOMDIR instance.
+fn generate_uid() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    format!("2.25.{}", nanos)
+}
@@ -1,12 +1,23 @@
+pub mod dicomdir;
+pub mod manifest;
+pub mod sanitize;
+pub mod state;
 mod support;
 
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 use dicom_dictionary_std::tags::{
-    MODALITY, PATIENT_ID, SERIES_DESCRIPTION, SERIES_INSTANCE_UID, SERIES_NUMBER,
+    MODALITY, PATIENT_ID, PIXEL_DATA, SERIES_DESCRIPTION, SERIES_INSTANCE_UID, SERIES_NUMBER,
     STUDY_DESCRIPTION, STUDY_INSTANCE_UID,
 };
-use dicom_object::InMemDicomObject;
+use dicom_object::{InMemDicomObject, OpenFileOptions};
+use rad_tools_common::fs::{copy_dicom, CopyError, CopyOptions};
+use tracing::{debug, info, trace, warn};
+use walkdir::WalkDir;
+
+pub use sanitize::{is_allowed, sanitize, sanitize_with, SanitizeOptions};
 
 const STUDY_INSTANCE_UID_UNKNOWN: &str = "STUDY_UID_UNKNOWN";
 const SERIES_INSTANCE_UID_UNKNOWN: &str = "SERIES_UID_UNKNOWN";
@@ -17,6 +28,16 @@ const MODALITY_UNKNOWN: &str = "MODALITY_UNKNOWN";
 pub enum Error {
     #[error("Patient ID is undefined or not set.")]
     PatientIdUnknown,
+    #[error("Error while walking the input directory")]
+    WalkDir(#[from] walkdir::Error),
+    #[error("Error occurred while copying: {0:#?} to {1:#?}")]
+    Copy(PathBuf, PathBuf, #[source] CopyError),
+    #[error("Error occurred while writing the DICOMDIR: {0:#?}")]
+    DicomDir(PathBuf, #[source] std::io::Error),
+    #[error("Error occurred while writing the manifest entry for: {0:#?}")]
+    Manifest(PathBuf, #[source] std::io::Error),
+    #[error("Error occurred while reading or writing the state: {0:#?}")]
+    State(PathBuf, #[source] std::io::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -90,8 +111,62 @@ impl Data {
     }
 }
 
-#[derive(Debug)]
-pub enum FromDicomObjectError {}
+/// Errors that can occur while converting a DICOM object into [`Data`].
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum FromDicomObjectError {
+    #[error("DICOM instance is missing the patient ID.")]
+    DicomInstanceMissingPatientId,
+    #[error("DICOM instance is missing the study instance UID.")]
+    DicomInstanceMissingStudyInstanceUid,
+    #[error("DICOM instance is missing the series instance UID.")]
+    DicomInstanceMissingSeriesInstanceUid,
+    #[error("DICOM instance is missing the modality.")]
+    DicomInstanceMissingModality,
+}
+
+impl Data {
+    /// Tries to convert a DICOM object into `Data`.
+    ///
+    /// In contrast to [`TryFromDicomObject::try_from_dicom_obj`], all required tags are read
+    /// before returning, so every missing tag is reported at once.
+    pub fn try_from_dicom_obj_all_errors(
+        obj: &InMemDicomObject,
+    ) -> std::result::Result<Self, Vec<FromDicomObjectError>> {
+        let mut errors = vec![];
+        let mut required = |tag, error| match support::get_str(obj, tag) {
+            Ok(s) => s,
+            Err(_) => {
+                errors.push(error);
+                String::new()
+            }
+        };
+        let patient_id = required(
+            PATIENT_ID,
+            FromDicomObjectError::DicomInstanceMissingPatientId,
+        );
+        let study_uid = required(
+            STUDY_INSTANCE_UID,
+            FromDicomObjectError::DicomInstanceMissingStudyInstanceUid,
+        );
+        let series_uid = required(
+            SERIES_INSTANCE_UID,
+            FromDicomObjectError::DicomInstanceMissingSeriesInstanceUid,
+        );
+        let modality = required(MODALITY, FromDicomObjectError::DicomInstanceMissingModality);
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(Data {
+            patient_id,
+            study_uid,
+            study_descr: support::get_str_or_default(obj, STUDY_DESCRIPTION),
+            series_uid,
+            series_descr: support::get_str_or_default(obj, SERIES_DESCRIPTION),
+            series_nr: support::get_str_or_default(obj, SERIES_NUMBER),
+            modality,
+        })
+    }
+}
 
 impl TryFromDicomObject for Data {
     type DicomObjectError = FromDicomObjectError;
@@ -99,23 +174,7 @@ impl TryFromDicomObject for Data {
     fn try_from_dicom_obj(
         obj: &InMemDicomObject,
     ) -> std::result::Result<Self, Self::DicomObjectError> {
-        let patient_id = support::get_str(obj, PATIENT_ID).unwrap();
-        let study_uid = support::get_str(obj, STUDY_INSTANCE_UID).unwrap();
-        let study_descr = support::get_str_or_default(obj, STUDY_DESCRIPTION);
-        let series_uid = support::get_str(obj, SERIES_INSTANCE_UID).unwrap();
-        let series_descr = support::get_str_or_default(obj, SERIES_DESCRIPTION);
-        let series_nr = support::get_str_or_default(obj, SERIES_NUMBER);
-        let modality = support::get_str(obj, MODALITY).unwrap();
-        let data = Data {
-            patient_id,
-            study_uid,
-            study_descr,
-            series_uid,
-            series_descr,
-            series_nr,
-            modality,
-        };
-        Ok(data)
+        Self::try_from_dicom_obj_all_errors(obj).map_err(|mut errors| errors.remove(0))
     }
 }
 
@@ -141,6 +200,8 @@ impl TryFromDicomObject for Data {
 /// - `modality`:
 ///     - modality, if not empty
 ///     - MODALITY_UNKNOWN
+///
+/// The values are used as is, use [`to_path_buf_with`] to sanitize them.
 pub fn to_path_buf<P>(d: &Data, p: P) -> Result<PathBuf>
 where
     P: AsRef<Path>,
@@ -180,17 +241,783 @@ where
     Ok(pb)
 }
 
+/// Create a file path based on the data, see [`to_path_buf`].
+///
+/// Each value is sanitized with [`sanitize_with`] and the given options before it's used as a
+/// directory name. A value that is empty after sanitizing is treated as if it's not set.
+pub fn to_path_buf_with<P>(d: &Data, p: P, options: &SanitizeOptions) -> Result<PathBuf>
+where
+    P: AsRef<Path>,
+{
+    let patient_id = sanitize_with(d.patient_id(), options);
+    if patient_id.is_empty() {
+        return Err(Error::PatientIdUnknown);
+    }
+    let first_or = |values: &[&str], unknown: &str| {
+        values
+            .iter()
+            .map(|v| sanitize_with(v, options))
+            .find(|v| !v.is_empty())
+            .unwrap_or_else(|| unknown.to_string())
+    };
+    let pb = p
+        .as_ref()
+        .join(patient_id)
+        .join(first_or(
+            &[d.study_descr(), d.study_uid()],
+            STUDY_INSTANCE_UID_UNKNOWN,
+        ))
+        .join(first_or(
+            &[d.series_descr(), d.series_uid()],
+            SERIES_INSTANCE_UID_UNKNOWN,
+        ))
+        .join(first_or(&[d.series_nr()], SERIES_NUMBER_UNKNOWN))
+        .join(first_or(&[d.modality()], MODALITY_UNKNOWN));
+    Ok(pb)
+}
+
+/// Options that control how the DICOM files are sorted.
+#[derive(Clone, Debug, Default)]
+pub struct SortOptions {
+    /// When set to `true`, sorting stops after the file that's currently being processed.
+    pub stop: Arc<AtomicBool>,
+    /// Write a DICOMDIR in the output directory after the files are copied.
+    pub dicomdir: bool,
+    /// Only sort DICOM files with one of these modalities. If empty, all modalities are sorted.
+    pub modalities: Vec<String>,
+    /// Follow symbolic links while walking the input directory.
+    ///
+    /// Symbolic links that point to one of their own parent directories are skipped to avoid
+    /// infinite loops.
+    pub follow_symlinks: bool,
+    /// Record the SHA-256 checksum of each copied file in this manifest (`sha256sum` format).
+    /// Entries of files that aren't copied again are kept.
+    pub manifest: Option<PathBuf>,
+    /// Stop after this number of DICOM files are copied.
+    pub limit: Option<usize>,
+    /// Only sort DICOM files with one of these Media Storage SOP Class UIDs. If empty, all SOP
+    /// classes are sorted.
+    pub sop_classes: Vec<String>,
+    /// Set the modification and access time of each copied file to those of the source file.
+    pub preserve_times: bool,
+    /// Record the sorted files in this state file, and skip files that were sorted in a previous
+    /// run and haven't been modified since.
+    pub state: Option<PathBuf>,
+    /// Options to sanitize the values used as directory names. If `None`, the values are used as
+    /// is.
+    pub sanitize: Option<SanitizeOptions>,
+}
+
+impl SortOptions {
+    /// Check if DICOM files with a modality are sorted.
+    fn is_modality_allowed(&self, modality: &str) -> bool {
+        self.modalities.is_empty()
+            || self
+                .modalities
+                .iter()
+                .any(|m| m.trim().eq_ignore_ascii_case(modality))
+    }
+
+    /// Check if DICOM files with a Media Storage SOP Class UID are sorted.
+    fn is_sop_class_allowed(&self, sop_class_uid: &str) -> bool {
+        let sop_class_uid = sop_class_uid.trim_end_matches('\0').trim();
+        self.sop_classes.is_empty() || self.sop_classes.iter().any(|c| c.trim() == sop_class_uid)
+    }
+}
+
+/// Sort the DICOM files in the input directory (recursively) into the output directory.
+///
+/// Each DICOM file is copied to the directory created by [`to_path_buf_with`].
+/// Files that can't be read as DICOM data are skipped.
+/// If requested, a DICOMDIR referencing all DICOM files in the output directory is written
+/// afterwards.
+///
+/// # Arguments
+///
+/// * `input`: directory from where DICOM files are read
+/// * `output`: directory to where DICOM files are copied to
+/// * `options`: options to control the sorting
+///
+/// returns: Result<usize>
+/// The number of copied DICOM files is returned, otherwise the first error that was detected.
+pub fn sort<P, Q>(input: P, output: Q, options: &SortOptions) -> Result<usize>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let input = input.as_ref();
+    let output = output.as_ref();
+    debug!("Input directory: {:#?}", input);
+    let mut manifest = match &options.manifest {
+        Some(p) => Some(manifest::Manifest::open(p, output)?),
+        None => None,
+    };
+    let mut state = match &options.state {
+        Some(p) => Some(state::State::open(p)?),
+        None => None,
+    };
+    let r = sort_files(input, output, options, manifest.as_mut(), state.as_mut());
+    // The manifest and the state are also saved if sorting failed, so they include the files that
+    // were copied and these files are skipped in a next run.
+    if let Some(manifest) = manifest.as_ref() {
+        match (&r, manifest.save()) {
+            (Err(_), Err(e)) => warn!("Unable to save the manifest: {}", e),
+            (_, saved) => saved?,
+        }
+    }
+    if let Some(state) = state.as_ref() {
+        match (&r, state.save()) {
+            (Err(_), Err(e)) => warn!("Unable to save the state: {}", e),
+            (_, saved) => saved?,
+        }
+    }
+    let ncopied = r?;
+    if options.dicomdir {
+        dicomdir::write_dicomdir(output)?;
+    }
+    Ok(ncopied)
+}
+
+/// Sort the DICOM files in the input directory (recursively) into the output directory, see
+/// [`sort`].
+///
+/// Each copied file is added to the manifest and the state, if they are given.
+fn sort_files(
+    input: &Path,
+    output: &Path,
+    options: &SortOptions,
+    mut manifest: Option<&mut manifest::Manifest>,
+    mut state: Option<&mut state::State>,
+) -> Result<usize> {
+    let copy_options = CopyOptions {
+        preserve_times: options.preserve_times,
+        ..Default::default()
+    };
+    let mut ncopied = 0;
+    for entry in WalkDir::new(input).follow_links(options.follow_symlinks) {
+        if options.stop.load(Ordering::SeqCst) {
+            info!("Sorting stopped, {} file(s) were copied.", ncopied);
+            break;
+        }
+        if options.limit.is_some_and(|limit| ncopied >= limit) {
+            info!(
+                "Sorting stopped, limit of {} copied file(s) reached.",
+                ncopied
+            );
+            break;
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) if e.loop_ancestor().is_some() => {
+                warn!("Skipping symbolic link loop: {}", e);
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        if state.as_ref().is_some_and(|state| state.is_unchanged(path)) {
+            debug!("Skipping {:#?}: unchanged since the previous run", path);
+            continue;
+        }
+        let obj = match OpenFileOptions::new()
+            .read_until(PIXEL_DATA)
+            .open_file(path)
+        {
+            Ok(obj) => obj,
+            Err(e) => {
+                debug!("Error reading DICOM data from {:#?}: {:#?}", path, e);
+                continue;
+            }
+        };
+        let sop_class_uid = obj.meta().media_storage_sop_class_uid();
+        if !options.is_sop_class_allowed(sop_class_uid) {
+            debug!(
+                "Skipping {:#?}: SOP class {} is not selected",
+                path, sop_class_uid
+            );
+            continue;
+        }
+
+        let data = match Data::try_from_dicom_obj(&obj) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("Unable to sort {:#?}: {}", path, e);
+                continue;
+            }
+        };
+        trace!("Data read from: {:#?}\n{:#?}", path, &data);
+        if !options.is_modality_allowed(data.modality()) {
+            debug!(
+                "Skipping {:#?}: modality {} is not selected",
+                path,
+                data.modality()
+            );
+            continue;
+        }
+        let odir = match &options.sanitize {
+            Some(sanitize) => to_path_buf_with(&data, output, sanitize)?,
+            None => to_path_buf(&data, output)?,
+        };
+        debug!("Output directory: {:#?}", &odir);
+        let ofile = odir.join(path.file_name().unwrap());
+        debug!("Output file: {:#?}", &ofile);
+        info!("Copying {:#?} to {:#?}", path, &ofile);
+        copy_dicom(path, &ofile, &copy_options)
+            .map_err(|e| Error::Copy(path.to_path_buf(), ofile.clone(), e))?;
+        if let Some(manifest) = manifest.as_deref_mut() {
+            manifest.add(&ofile)?;
+        }
+        if let Some(state) = state.as_deref_mut() {
+            state.insert(path);
+        }
+        ncopied += 1;
+    }
+    Ok(ncopied)
+}
+
 #[cfg(test)]
 mod test {
-    use std::path::PathBuf;
+    use std::fs::FileTimes;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::Ordering;
+    use std::time::{Duration, SystemTime};
 
+    use dicom_core::VR;
+    use dicom_dictionary_std::tags::{
+        DIRECTORY_RECORD_SEQUENCE, DIRECTORY_RECORD_TYPE, MODALITY,
+        OFFSET_OF_REFERENCED_LOWER_LEVEL_DIRECTORY_ENTITY,
+        OFFSET_OF_THE_FIRST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+        OFFSET_OF_THE_LAST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+        OFFSET_OF_THE_NEXT_DIRECTORY_RECORD, PATIENT_ID, SERIES_INSTANCE_UID, SOP_CLASS_UID,
+        SOP_INSTANCE_UID, STUDY_INSTANCE_UID,
+    };
+    use dicom_dictionary_std::uids::{
+        BASIC_TEXT_SR_STORAGE, CT_IMAGE_STORAGE, RAW_DATA_STORAGE, RT_STRUCTURE_SET_STORAGE,
+    };
+    use dicom_object::{FileMetaTableBuilder, InMemDicomObject, OpenFileOptions};
     use tracing::error;
 
     use crate::{
-        Data, MODALITY_UNKNOWN, SERIES_INSTANCE_UID_UNKNOWN, SERIES_NUMBER_UNKNOWN,
+        Data, Error, FromDicomObjectError, SanitizeOptions, SortOptions, TryFromDicomObject,
+        MODALITY_UNKNOWN, SERIES_INSTANCE_UID_UNKNOWN, SERIES_NUMBER_UNKNOWN,
         STUDY_INSTANCE_UID_UNKNOWN,
     };
 
+    /// Create an empty test directory in the temporary directory.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join("rad_tools_dcm_sort").join(name);
+        if dir.is_dir() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Write a minimal DICOM file with the data required for sorting.
+    fn write_test_file(p: &Path, patient_id: &str, sop_class_uid: &str, modality: &str) {
+        let mut obj = InMemDicomObject::new_empty();
+        let instance_uid = format!("1.2.3.{}", p.file_stem().unwrap().to_string_lossy());
+        obj.put_str(PATIENT_ID, VR::LO, patient_id);
+        obj.put_str(STUDY_INSTANCE_UID, VR::UI, "1.2.3.1");
+        obj.put_str(SERIES_INSTANCE_UID, VR::UI, "1.2.3.1.1");
+        obj.put_str(SOP_CLASS_UID, VR::UI, sop_class_uid);
+        obj.put_str(SOP_INSTANCE_UID, VR::UI, instance_uid);
+        obj.put_str(MODALITY, VR::CS, modality);
+        let file_obj = obj
+            .with_meta(
+                FileMetaTableBuilder::new()
+                    .transfer_syntax(dicom_transfer_syntax_registry::default().erased().uid())
+                    .media_storage_sop_class_uid(sop_class_uid),
+            )
+            .unwrap();
+        file_obj.write_to_file(p).unwrap();
+    }
+
+    /// Count the files in a directory (recursively).
+    fn count_files(p: &Path) -> usize {
+        walkdir::WalkDir::new(p)
+            .into_iter()
+            .filter(|e| e.as_ref().unwrap().path().is_file())
+            .count()
+    }
+
+    #[test]
+    fn try_from_dicom_obj_all_errors() {
+        let mut obj = InMemDicomObject::new_empty();
+        obj.put_str(STUDY_INSTANCE_UID, VR::UI, "1.2.3.1");
+        obj.put_str(SERIES_INSTANCE_UID, VR::UI, "1.2.3.1.1");
+
+        let errors = Data::try_from_dicom_obj_all_errors(&obj).unwrap_err();
+        assert_eq!(
+            vec![
+                FromDicomObjectError::DicomInstanceMissingPatientId,
+                FromDicomObjectError::DicomInstanceMissingModality,
+            ],
+            errors
+        );
+        assert_eq!(
+            FromDicomObjectError::DicomInstanceMissingPatientId,
+            Data::try_from_dicom_obj(&obj).unwrap_err()
+        );
+
+        obj.put_str(PATIENT_ID, VR::LO, "pt_id");
+        obj.put_str(MODALITY, VR::CS, "CT");
+        let data = Data::try_from_dicom_obj_all_errors(&obj).unwrap();
+        assert_eq!("pt_id", data.patient_id());
+        assert_eq!("CT", data.modality());
+        assert_eq!("", data.series_descr());
+    }
+
+    #[test]
+    fn sort() {
+        let idir = test_dir("sort_input");
+        let odir = test_dir("sort_output");
+        for i in 0..3 {
+            write_test_file(
+                &idir.join(format!("{}.dcm", i)),
+                "pt_id",
+                CT_IMAGE_STORAGE,
+                "CT",
+            );
+        }
+        std::fs::write(idir.join("dummy.txt"), "Rust test: dcm_sort").unwrap();
+
+        let n = super::sort(&idir, &odir, &SortOptions::default()).unwrap();
+        assert_eq!(3, n);
+        assert_eq!(3, count_files(&odir));
+
+        std::fs::remove_dir_all(&idir).unwrap();
+        std::fs::remove_dir_all(&odir).unwrap();
+    }
+
+    #[test]
+    fn sort_modalities() {
+        let idir = test_dir("sort_modalities_input");
+        let odir = test_dir("sort_modalities_output");
+        for i in 0..3 {
+            write_test_file(
+                &idir.join(format!("{}.dcm", i)),
+                "pt_id",
+                CT_IMAGE_STORAGE,
+                "CT",
+            );
+        }
+        write_test_file(
+            &idir.join("3.dcm"),
+            "pt_id",
+            RT_STRUCTURE_SET_STORAGE,
+            "RTSTRUCT",
+        );
+
+        let options = SortOptions {
+            modalities: vec!["RTSTRUCT".to_string()],
+            ..Default::default()
+        };
+        let n = super::sort(&idir, &odir, &options).unwrap();
+        assert_eq!(1, n);
+        assert_eq!(1, count_files(&odir));
+        let rtstruct_dir = odir
+            .join("pt_id")
+            .join("1.2.3.1")
+            .join("1.2.3.1.1")
+            .join(SERIES_NUMBER_UNKNOWN)
+            .join("RTSTRUCT");
+        assert!(rtstruct_dir.join("3.dcm").is_file());
+
+        std::fs::remove_dir_all(&idir).unwrap();
+        std::fs::remove_dir_all(&odir).unwrap();
+    }
+
+    #[test]
+    fn sort_sop_classes() {
+        let idir = test_dir("sort_sop_classes_input");
+        let odir = test_dir("sort_sop_classes_output");
+        for i in 0..3 {
+            write_test_file(
+                &idir.join(format!("{}.dcm", i)),
+                "pt_id",
+                CT_IMAGE_STORAGE,
+                "CT",
+            );
+        }
+        write_test_file(
+            &idir.join("3.dcm"),
+            "pt_id",
+            RT_STRUCTURE_SET_STORAGE,
+            "RTSTRUCT",
+        );
+
+        let options = SortOptions {
+            sop_classes: vec![CT_IMAGE_STORAGE.to_string()],
+            ..Default::default()
+        };
+        let n = super::sort(&idir, &odir, &options).unwrap();
+        assert_eq!(3, n);
+        assert_eq!(3, count_files(&odir));
+        let ct_dir = odir
+            .join("pt_id")
+            .join("1.2.3.1")
+            .join("1.2.3.1.1")
+            .join(SERIES_NUMBER_UNKNOWN)
+            .join("CT");
+        assert_eq!(3, count_files(&ct_dir));
+
+        std::fs::remove_dir_all(&idir).unwrap();
+        std::fs::remove_dir_all(&odir).unwrap();
+    }
+
+    #[test]
+    fn sort_preserve_times() {
+        let idir = test_dir("sort_preserve_times_input");
+        let odir = test_dir("sort_preserve_times_output");
+        let ifile = idir.join("0.dcm");
+        write_test_file(&ifile, "pt_id", CT_IMAGE_STORAGE, "CT");
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+        std::fs::File::options()
+            .write(true)
+            .open(&ifile)
+            .unwrap()
+            .set_times(FileTimes::new().set_modified(modified))
+            .unwrap();
+
+        let options = SortOptions {
+            preserve_times: true,
+            ..Default::default()
+        };
+        let n = super::sort(&idir, &odir, &options).unwrap();
+        assert_eq!(1, n);
+        let ofile = odir
+            .join("pt_id")
+            .join("1.2.3.1")
+            .join("1.2.3.1.1")
+            .join(SERIES_NUMBER_UNKNOWN)
+            .join("CT")
+            .join("0.dcm");
+        assert_eq!(
+            modified,
+            std::fs::metadata(&ofile).unwrap().modified().unwrap()
+        );
+
+        std::fs::remove_dir_all(&idir).unwrap();
+        std::fs::remove_dir_all(&odir).unwrap();
+    }
+
+    #[test]
+    fn sort_limit() {
+        let idir = test_dir("sort_limit_input");
+        let odir = test_dir("sort_limit_output");
+        for i in 0..5 {
+            write_test_file(
+                &idir.join(format!("{}.dcm", i)),
+                "pt_id",
+                CT_IMAGE_STORAGE,
+                "CT",
+            );
+        }
+
+        let options = SortOptions {
+            limit: Some(2),
+            ..Default::default()
+        };
+        let n = super::sort(&idir, &odir, &options).unwrap();
+        assert_eq!(2, n);
+        assert_eq!(2, count_files(&odir));
+
+        std::fs::remove_dir_all(&idir).unwrap();
+        std::fs::remove_dir_all(&odir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sort_follow_symlinks() {
+        let idir = test_dir("sort_symlinks_input");
+        let odir = test_dir("sort_symlinks_output");
+        let ldir = test_dir("sort_symlinks_linked");
+        write_test_file(&ldir.join("0.dcm"), "pt_id", CT_IMAGE_STORAGE, "CT");
+        std::os::unix::fs::symlink(&ldir, idir.join("linked")).unwrap();
+        // A link to a parent directory must not result in an infinite loop.
+        std::os::unix::fs::symlink(&idir, idir.join("loop")).unwrap();
+
+        let n = super::sort(&idir, &odir, &SortOptions::default()).unwrap();
+        assert_eq!(0, n);
+        assert_eq!(0, count_files(&odir));
+
+        let options = SortOptions {
+            follow_symlinks: true,
+            ..Default::default()
+        };
+        let n = super::sort(&idir, &odir, &options).unwrap();
+        assert_eq!(1, n);
+        assert_eq!(1, count_files(&odir));
+
+        std::fs::remove_dir_all(&idir).unwrap();
+        std::fs::remove_dir_all(&odir).unwrap();
+        std::fs::remove_dir_all(&ldir).unwrap();
+    }
+
+    #[test]
+    fn sort_manifest() {
+        let idir = test_dir("sort_manifest_input");
+        let odir = test_dir("sort_manifest_output");
+        let mdir = test_dir("sort_manifest");
+        for i in 0..3 {
+            write_test_file(
+                &idir.join(format!("{}.dcm", i)),
+                "pt_id",
+                CT_IMAGE_STORAGE,
+                "CT",
+            );
+        }
+
+        let manifest_path = mdir.join("manifest.sha256");
+        let options = SortOptions {
+            manifest: Some(manifest_path.clone()),
+            ..Default::default()
+        };
+        let n = super::sort(&idir, &odir, &options).unwrap();
+        assert_eq!(3, n);
+
+        let manifest = std::fs::read_to_string(&manifest_path).unwrap();
+        let lines: Vec<&str> = manifest.lines().collect();
+        assert_eq!(3, lines.len());
+        for line in lines {
+            let (hash, rel_path) = line.split_once("  ").unwrap();
+            let ofile = odir.join(rel_path);
+            assert!(ofile.is_file());
+            assert_eq!(rad_tools_common::fs::sha256(&ofile).unwrap(), hash);
+        }
+
+        // Sorting again replaces the entries instead of appending them.
+        let n = super::sort(&idir, &odir, &options).unwrap();
+        assert_eq!(3, n);
+        let manifest = std::fs::read_to_string(&manifest_path).unwrap();
+        assert_eq!(3, manifest.lines().count());
+
+        std::fs::remove_dir_all(&idir).unwrap();
+        std::fs::remove_dir_all(&odir).unwrap();
+        std::fs::remove_dir_all(&mdir).unwrap();
+    }
+
+    #[test]
+    fn sort_state() {
+        let idir = test_dir("sort_state_input");
+        let odir = test_dir("sort_state_output");
+        let sdir = test_dir("sort_state");
+        for i in 0..2 {
+            write_test_file(
+                &idir.join(format!("{}.dcm", i)),
+                "pt_id",
+                CT_IMAGE_STORAGE,
+                "CT",
+            );
+        }
+
+        let options = SortOptions {
+            state: Some(sdir.join("state.json")),
+            ..Default::default()
+        };
+        let n = super::sort(&idir, &odir, &options).unwrap();
+        assert_eq!(2, n);
+
+        // Only the new file is sorted in the second run.
+        write_test_file(&idir.join("2.dcm"), "pt_id", CT_IMAGE_STORAGE, "CT");
+        let n = super::sort(&idir, &odir, &options).unwrap();
+        assert_eq!(1, n);
+        assert_eq!(3, count_files(&odir));
+
+        std::fs::remove_dir_all(&idir).unwrap();
+        std::fs::remove_dir_all(&odir).unwrap();
+        std::fs::remove_dir_all(&sdir).unwrap();
+    }
+
+    #[test]
+    fn sort_state_saved_on_error() {
+        let idir = test_dir("sort_state_saved_on_error_input");
+        let odir = test_dir("sort_state_saved_on_error_output");
+        let sdir = test_dir("sort_state_saved_on_error");
+        for (i, patient_id) in ["a", "a", "b"].iter().enumerate() {
+            write_test_file(
+                &idir.join(format!("{}.dcm", i)),
+                patient_id,
+                CT_IMAGE_STORAGE,
+                "CT",
+            );
+        }
+        // A file where the output directory of patient b is created makes sorting fail.
+        std::fs::write(odir.join("b"), "").unwrap();
+
+        let options = SortOptions {
+            state: Some(sdir.join("state.json")),
+            ..Default::default()
+        };
+        assert!(matches!(
+            super::sort(&idir, &odir, &options),
+            Err(Error::Copy(..))
+        ));
+        assert!(sdir.join("state.json").is_file());
+        let ncopied = if odir.join("a").is_dir() {
+            count_files(&odir.join("a"))
+        } else {
+            0
+        };
+
+        // The files copied before the error are skipped in the next run.
+        std::fs::remove_file(odir.join("b")).unwrap();
+        let n = super::sort(&idir, &odir, &options).unwrap();
+        assert_eq!(3 - ncopied, n);
+        assert_eq!(3, count_files(&odir));
+
+        std::fs::remove_dir_all(&idir).unwrap();
+        std::fs::remove_dir_all(&odir).unwrap();
+        std::fs::remove_dir_all(&sdir).unwrap();
+    }
+
+    #[test]
+    fn sort_dicomdir() {
+        let idir = test_dir("sort_dicomdir_input");
+        let odir = test_dir("sort_dicomdir_output");
+        for i in 0..3 {
+            write_test_file(
+                &idir.join(format!("{}.dcm", i)),
+                "pt_id",
+                CT_IMAGE_STORAGE,
+                "CT",
+            );
+        }
+        write_test_file(
+            &idir.join("3.dcm"),
+            "pt_id",
+            RT_STRUCTURE_SET_STORAGE,
+            "RTSTRUCT",
+        );
+        write_test_file(&idir.join("4.dcm"), "pt_id", BASIC_TEXT_SR_STORAGE, "SR");
+        // Not an image and no known directory record type, so it's not referenced.
+        write_test_file(&idir.join("5.dcm"), "pt_id", RAW_DATA_STORAGE, "RAW");
+
+        let options = SortOptions {
+            dicomdir: true,
+            ..Default::default()
+        };
+        let n = super::sort(&idir, &odir, &options).unwrap();
+        assert_eq!(6, n);
+        let path = odir.join(crate::dicomdir::DICOMDIR);
+        assert!(path.is_file());
+
+        let obj = OpenFileOptions::new().open_file(&path).unwrap();
+        let records = obj
+            .element(DIRECTORY_RECORD_SEQUENCE)
+            .unwrap()
+            .items()
+            .unwrap();
+        let record_types: Vec<String> = records
+            .iter()
+            .map(|r| {
+                r.element(DIRECTORY_RECORD_TYPE)
+                    .unwrap()
+                    .to_str()
+                    .unwrap()
+                    .trim()
+                    .to_string()
+            })
+            .collect();
+        let count = |t: &str| record_types.iter().filter(|rt| rt.as_str() == t).count();
+        assert_eq!(1, count("PATIENT"));
+        assert_eq!(1, count("STUDY"));
+        assert_eq!(1, count("SERIES"));
+        assert_eq!(3, count("IMAGE"));
+        assert_eq!(1, count("RT STRUCTURE"));
+        assert_eq!(1, count("SR DOCUMENT"));
+        assert_eq!(8, records.len());
+
+        // Each offset points to the start of a directory record item, and each record is
+        // referenced once.
+        let bytes = std::fs::read(&path).unwrap();
+        let offset = |obj: &InMemDicomObject, tag| {
+            obj.element(tag).unwrap().to_int::<u32>().unwrap() as usize
+        };
+        let root: &InMemDicomObject = &obj;
+        let mut offsets = vec![
+            offset(
+                root,
+                OFFSET_OF_THE_FIRST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+            ),
+            offset(
+                root,
+                OFFSET_OF_THE_LAST_DIRECTORY_RECORD_OF_THE_ROOT_DIRECTORY_ENTITY,
+            ),
+        ];
+        for record in records {
+            offsets.push(offset(record, OFFSET_OF_THE_NEXT_DIRECTORY_RECORD));
+            offsets.push(offset(
+                record,
+                OFFSET_OF_REFERENCED_LOWER_LEVEL_DIRECTORY_ENTITY,
+            ));
+        }
+        offsets.retain(|o| *o != 0);
+        for o in &offsets {
+            assert_eq!([0xFE, 0xFF, 0x00, 0xE0], bytes[*o..*o + 4]);
+        }
+        offsets.sort();
+        offsets.dedup();
+        assert_eq!(records.len(), offsets.len());
+
+        std::fs::remove_dir_all(&idir).unwrap();
+        std::fs::remove_dir_all(&odir).unwrap();
+    }
+
+    #[test]
+    fn sort_stopped() {
+        let idir = test_dir("sort_stopped_input");
+        let odir = test_dir("sort_stopped_output");
+        for i in 0..3 {
+            write_test_file(
+                &idir.join(format!("{}.dcm", i)),
+                "pt_id",
+                CT_IMAGE_STORAGE,
+                "CT",
+            );
+        }
+
+        let options = SortOptions::default();
+        options.stop.store(true, Ordering::SeqCst);
+        let n = super::sort(&idir, &odir, &options).unwrap();
+        assert_eq!(0, n);
+        assert_eq!(0, count_files(&odir));
+
+        std::fs::remove_dir_all(&idir).unwrap();
+        std::fs::remove_dir_all(&odir).unwrap();
+    }
+
+    #[test]
+    fn to_path_buf_with() {
+        let data = Data {
+            patient_id: "pt_id".into(),
+            study_uid: "study".into(),
+            study_descr: "Thorax/Abdomen".into(),
+            series_uid: "series".into(),
+            series_descr: "**".into(),
+            series_nr: "1".into(),
+            modality: "CT".into(),
+        };
+        let options = SanitizeOptions {
+            replacement: Some('-'),
+            collapse: true,
+            trim: true,
+        };
+        let buf = super::to_path_buf_with(&data, ".", &options).unwrap();
+        let expected = PathBuf::new()
+            .join(".")
+            .join("pt_id")
+            .join("Thorax-Abdomen")
+            .join("series")
+            .join("1")
+            .join("CT");
+        assert_eq!(expected, buf);
+    }
+
     #[test]
     fn to_path_buf() {
         let datas = [
@@ -1,9 +1,10 @@
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+
 use clap::Parser;
-use dcm_sort::TryFromDicomObject;
-use dicom_dictionary_std::tags::PIXEL_DATA;
-use dicom_object::OpenFileOptions;
-use tracing::{debug, error, info, trace, Level};
-use walkdir::WalkDir;
+use dcm_sort::{SanitizeOptions, SortOptions};
+use rad_tools_common::get_log_level;
+use tracing::{error, info, trace, warn};
 
 /// A command line interface (CLI) application to sort DICOM files into a set of subdirectories.
 ///
@@ -44,6 +45,51 @@ struct Cli {
     /// Directory to where DICOM files are copied to.
     #[arg(short, long, value_name = "DIR")]
     output: String,
+    /// Write a DICOMDIR in the output directory, referencing the sorted DICOM files.
+    #[arg(long, default_value_t = false)]
+    dicomdir: bool,
+    /// Only sort DICOM files with one of these (comma separated) modalities, e.g. CT,RTSTRUCT.
+    /// If unspecified, all modalities are sorted.
+    #[arg(short, long, value_delimiter = ',', value_name = "MODALITY")]
+    modality: Vec<String>,
+    /// Follow symbolic links while walking the input directory.
+    #[arg(long, default_value_t = false)]
+    follow_symlinks: bool,
+    /// Record the SHA-256 checksum of each copied file in a manifest (sha256sum format).
+    #[arg(long, value_name = "FILE")]
+    manifest: Option<PathBuf>,
+    /// Stop after this number of DICOM files are copied.
+    #[arg(short, long, value_name = "N")]
+    limit: Option<usize>,
+    /// Only sort DICOM files with this Media Storage SOP Class UID (can be repeated).
+    /// If unspecified, all SOP classes are sorted.
+    #[arg(long, value_name = "UID")]
+    sop_class: Vec<String>,
+    /// Preserve the modification and access time of the copied files.
+    #[arg(long, default_value_t = false)]
+    preserve_times: bool,
+    /// Record the sorted files in a state file (JSON), and skip files that were sorted in a
+    /// previous run and haven't been modified since.
+    #[arg(long, value_name = "FILE")]
+    state: Option<PathBuf>,
+    /// Sanitize the directory names: replace the characters that aren't allowed in a directory
+    /// name (<>:"/\|?* and control characters) by this character.
+    #[arg(long, value_name = "CHAR", value_parser = parse_replacement)]
+    sanitize_replacement: Option<char>,
+    /// Sanitize the directory names: remove the characters that aren't allowed in a directory
+    /// name.
+    #[arg(long, default_value_t = false, conflicts_with = "sanitize_replacement")]
+    sanitize_remove: bool,
+    /// Sanitize the directory names and collapse runs of the replacement character.
+    #[arg(long, default_value_t = false)]
+    sanitize_collapse: bool,
+    /// Sanitize the directory names and trim leading and trailing whitespace, dots and
+    /// replacement characters.
+    #[arg(long, default_value_t = false)]
+    sanitize_trim: bool,
+    /// Enable logging at INFO level.
+    #[arg(short, long, default_value_t = false)]
+    verbose: bool,
     /// Enable logging at DEBUG level.
     #[arg(long, default_value_t = false)]
     debug: bool,
@@ -54,13 +100,7 @@ struct Cli {
 
 fn main() {
     let cli = Cli::parse();
-    let level = if cli.trace {
-        Level::TRACE
-    } else if cli.debug {
-        Level::DEBUG
-    } else {
-        Level::WARN
-    };
+    let level = get_log_level!(cli);
     tracing_subscriber::fmt()
         .with_thread_ids(true)
         .with_target(true)
@@ -69,40 +109,60 @@ fn main() {
 
     trace!("Commandline arguments: {:#?}", &cli);
 
-    let entries = WalkDir::new(&cli.input);
+    let options = SortOptions {
+        dicomdir: cli.dicomdir,
+        modalities: cli.modality.clone(),
+        follow_symlinks: cli.follow_symlinks,
+        manifest: cli.manifest.clone(),
+        limit: cli.limit,
+        sop_classes: cli.sop_class.clone(),
+        preserve_times: cli.preserve_times,
+        state: cli.state.clone(),
+        sanitize: sanitize_options(&cli),
+        ..Default::default()
+    };
+    let stop = options.stop.clone();
+    ctrlc::set_handler(move || {
+        warn!("Stop requested, finishing the file that's currently being processed.");
+        stop.store(true, Ordering::SeqCst);
+    })
+    .expect("Unable to set the signal handler.");
 
-    debug!("Input directory: {:#?}", &cli.input);
-    for entry in entries {
-        let entry = entry.expect("Unable to get file path entry.");
-        let path = entry.path();
-        if !path.is_file() {
-            continue;
-        }
-        let r = OpenFileOptions::new()
-            .read_until(PIXEL_DATA)
-            // .read_until(SERIES_DESCRIPTION)
-            .open_file(path);
-        if r.as_ref().is_err() && level >= Level::INFO {
-            error!("Error reading DICOM data from: {:#?}", r.as_ref().err());
-            continue;
+    match dcm_sort::sort(&cli.input, &cli.output, &options) {
+        Ok(n) => info!("Sorted {} DICOM file(s).", n),
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
         }
-        let obj = r.unwrap();
+    }
+}
+
+/// Get the sanitize options, `None` if none of the sanitize arguments are used.
+fn sanitize_options(cli: &Cli) -> Option<SanitizeOptions> {
+    if cli.sanitize_replacement.is_none()
+        && !cli.sanitize_remove
+        && !cli.sanitize_collapse
+        && !cli.sanitize_trim
+    {
+        return None;
+    }
+    Some(SanitizeOptions {
+        replacement: if cli.sanitize_remove {
+            None
+        } else {
+            Some(cli.sanitize_replacement.unwrap_or('_'))
+        },
+        collapse: cli.sanitize_collapse,
+        trim: cli.sanitize_trim,
+    })
+}
 
-        let data = dcm_sort::Data::try_from_dicom_obj(&obj)
-            .expect("Unable to create Data from DicomObject");
-        trace!("Data read from: {:#?}\n{:#?}", path, &data);
-        let odir = dcm_sort::to_path_buf(&data, &cli.output).unwrap();
-        debug!("Output directory: {:#?}", &odir);
-        std::fs::create_dir_all(&odir)
-            .unwrap_or_else(|e| panic!("Error occurred while creating: {:#?}\n{:#?}", &odir, e));
-        let ofile = odir.join(path.file_name().unwrap());
-        debug!("Output file: {:#?}", &ofile);
-        info!("Copying {:#?} to {:#?}", path, &ofile);
-        std::fs::copy(path, &ofile).unwrap_or_else(|e| {
-            panic!(
-                "Error occurred while copying: {:#?} to {:#?}\n{:#?}",
-                &path, &ofile, e
-            )
-        });
+/// Parse the replacement character, which must be allowed in a directory name itself.
+fn parse_replacement(s: &str) -> Result<char, String> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if dcm_sort::is_allowed(c) => Ok(c),
+        (Some(c), None) => Err(format!("{:?} isn't allowed in a directory name", c)),
+        _ => Err("expected a single character".to_string()),
     }
 }
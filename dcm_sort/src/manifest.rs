@@ -0,0 +1,87 @@
+//! Checksum manifest of the sorted DICOM files.
+//!
+//! The manifest uses the format of `sha256sum`: `<sha256>  <relative path>`, so the output
+//! directory can be verified with `sha256sum -c`. The manifest has one line per relative path:
+//! the checksum of a file that is copied again replaces its previous checksum.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use rad_tools_common::fs::sha256;
+use tracing::trace;
+
+use crate::{Error, Result};
+
+/// Manifest with the checksums of the copied files.
+#[derive(Debug)]
+pub struct Manifest {
+    /// Path of the manifest file.
+    path: PathBuf,
+    /// Directory to which the paths in the manifest are relative.
+    root: PathBuf,
+    /// Checksum of each file, by relative path.
+    entries: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    /// Open a manifest, an empty manifest is used if it doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: path of the manifest file
+    /// * `root`: directory to which the paths in the manifest are relative
+    pub fn open<P, Q>(path: P, root: Q) -> Result<Self>
+    where
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        let mut entries = BTreeMap::new();
+        if path.is_file() {
+            let s = std::fs::read_to_string(&path).map_err(|e| Error::Manifest(path.clone(), e))?;
+            for line in s.lines() {
+                if let Some((hash, rel_path)) = line.split_once("  ") {
+                    entries.insert(rel_path.to_string(), hash.to_string());
+                }
+            }
+        }
+        Ok(Self {
+            path,
+            root: root.as_ref().to_path_buf(),
+            entries,
+        })
+    }
+
+    /// Add the checksum of a file to the manifest, replacing a previous checksum of the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `p`: file inside the root directory of the manifest
+    pub fn add<P>(&mut self, p: P) -> Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let p = p.as_ref();
+        let hash = sha256(p).map_err(|e| Error::Manifest(p.to_path_buf(), e))?;
+        let rel_path = p
+            .strip_prefix(&self.root)
+            .unwrap_or(p)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        trace!("Manifest entry: {}  {}", &hash, &rel_path);
+        self.entries.insert(rel_path, hash);
+        Ok(())
+    }
+
+    /// Write the manifest to its file.
+    pub fn save(&self) -> Result<()> {
+        let s: String = self
+            .entries
+            .iter()
+            .map(|(rel_path, hash)| format!("{}  {}\n", hash, rel_path))
+            .collect();
+        std::fs::write(&self.path, s).map_err(|e| Error::Manifest(self.path.clone(), e))
+    }
+}
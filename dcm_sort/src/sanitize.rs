@@ -0,0 +1,134 @@
+//! Sanitize the DICOM values that are used as directory names.
+
+/// Characters that aren't allowed in a file or directory name on at least one platform.
+const DISALLOWED: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// Options that control how a value is sanitized.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SanitizeOptions {
+    /// Character that replaces disallowed characters. If `None`, disallowed characters are
+    /// removed.
+    pub replacement: Option<char>,
+    /// Collapse runs of the replacement character into a single one.
+    pub collapse: bool,
+    /// Trim leading and trailing whitespace, dots and replacement characters.
+    pub trim: bool,
+}
+
+impl Default for SanitizeOptions {
+    fn default() -> Self {
+        Self {
+            replacement: Some('_'),
+            collapse: false,
+            trim: false,
+        }
+    }
+}
+
+/// Check if a character is allowed in a file or directory name.
+///
+/// The characters `<>:"/\|?*` and control characters aren't allowed.
+pub fn is_allowed(c: char) -> bool {
+    !DISALLOWED.contains(&c) && !c.is_control()
+}
+
+/// Sanitize a value so it can be used as a file or directory name.
+///
+/// Disallowed characters are replaced by `_`, see [`sanitize_with`].
+pub fn sanitize(s: &str) -> String {
+    sanitize_with(s, &SanitizeOptions::default())
+}
+
+/// Sanitize a value so it can be used as a file or directory name.
+///
+/// Disallowed characters (`<>:"/\|?*` and control characters) are replaced by the replacement
+/// character or removed. If enabled in the options, runs of the replacement character are
+/// collapsed into a single one, and leading and trailing whitespace, dots and replacement
+/// characters are trimmed.
+///
+/// # Arguments
+///
+/// * `s`: value to sanitize
+/// * `options`: options to control the sanitizing
+///
+/// returns: String
+/// The sanitized value, which can be empty.
+pub fn sanitize_with(s: &str, options: &SanitizeOptions) -> String {
+    let mut sanitized = String::with_capacity(s.len());
+    for c in s.chars() {
+        let c = if !is_allowed(c) {
+            match options.replacement {
+                Some(r) => r,
+                None => continue,
+            }
+        } else {
+            c
+        };
+        if options.collapse && Some(c) == options.replacement && sanitized.ends_with(c) {
+            continue;
+        }
+        sanitized.push(c);
+    }
+    if !options.trim {
+        return sanitized;
+    }
+    sanitized
+        .trim_matches(|c: char| c.is_whitespace() || c == '.' || Some(c) == options.replacement)
+        .to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_allowed, sanitize, sanitize_with, SanitizeOptions};
+
+    #[test]
+    fn sanitize_default() {
+        assert_eq!("CT_Thorax__1", sanitize("CT/Thorax::1"));
+        assert_eq!(" Head__Neck. ", sanitize(" Head__Neck. "));
+        assert_eq!("1.2.3.4", sanitize("1.2.3.4"));
+        assert_eq!("__", sanitize("//"));
+    }
+
+    #[test]
+    fn sanitize_with_collapse_trim() {
+        let options = SanitizeOptions {
+            collapse: true,
+            trim: true,
+            ..Default::default()
+        };
+        assert_eq!("CT_Thorax_1", sanitize_with("CT/Thorax::1", &options));
+        assert_eq!("Head_Neck", sanitize_with(" Head__Neck. ", &options));
+        assert_eq!("", sanitize_with("//", &options));
+    }
+
+    #[test]
+    fn is_allowed_chars() {
+        assert!(is_allowed('_'));
+        assert!(is_allowed('-'));
+        assert!(!is_allowed('/'));
+        assert!(!is_allowed('\n'));
+    }
+
+    #[test]
+    fn sanitize_with_dash() {
+        let options = SanitizeOptions {
+            replacement: Some('-'),
+            collapse: true,
+            trim: true,
+        };
+        assert_eq!("CT-Thorax-1", sanitize_with("CT/Thorax:?1", &options));
+        assert_eq!("a-b", sanitize_with("-a--b-", &options));
+        assert_eq!("a_b", sanitize_with("a_b", &options));
+    }
+
+    #[test]
+    fn sanitize_with_delete() {
+        let options = SanitizeOptions {
+            replacement: None,
+            ..Default::default()
+        };
+        assert_eq!("CTThorax1", sanitize_with("CT/Thorax:?1", &options));
+        assert_eq!("a__b", sanitize_with("<a__b>", &options));
+        assert_eq!("", sanitize_with("*?", &options));
+    }
+}
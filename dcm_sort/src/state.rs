@@ -0,0 +1,97 @@
+//! State of the DICOM files that were sorted in previous runs.
+//!
+//! The state is stored as a JSON map from the (canonical) path of each sorted input file to its
+//! last modification time, in nanoseconds since the Unix epoch. Files that are unchanged since
+//! they were sorted can be skipped in a next run.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use tracing::trace;
+
+use crate::{Error, Result};
+
+/// State of the sorted input files.
+#[derive(Debug)]
+pub struct State {
+    /// Path of the state file.
+    path: PathBuf,
+    /// Input files that were sorted, with their modification time.
+    entries: BTreeMap<String, u64>,
+}
+
+impl State {
+    /// Open a state file, an empty state is used if it doesn't exist.
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: path of the state file
+    pub fn open<P>(path: P) -> Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref().to_path_buf();
+        let entries = if path.is_file() {
+            let s = std::fs::read_to_string(&path).map_err(|e| Error::State(path.clone(), e))?;
+            serde_json::from_str(&s).map_err(|e| Error::State(path.clone(), e.into()))?
+        } else {
+            BTreeMap::new()
+        };
+        Ok(Self { path, entries })
+    }
+
+    /// Check if a file was sorted before and hasn't been modified since.
+    ///
+    /// # Arguments
+    ///
+    /// * `p`: input file
+    pub fn is_unchanged<P>(&self, p: P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        let p = p.as_ref();
+        match modified(p) {
+            Some(modified) => self.entries.get(&key(p)) == Some(&modified),
+            None => false,
+        }
+    }
+
+    /// Record that a file was sorted.
+    ///
+    /// # Arguments
+    ///
+    /// * `p`: input file
+    pub fn insert<P>(&mut self, p: P)
+    where
+        P: AsRef<Path>,
+    {
+        let p = p.as_ref();
+        if let Some(modified) = modified(p) {
+            trace!("State entry: {:#?} [{}]", p, modified);
+            self.entries.insert(key(p), modified);
+        }
+    }
+
+    /// Write the state to its file.
+    pub fn save(&self) -> Result<()> {
+        let s = serde_json::to_string_pretty(&self.entries)
+            .map_err(|e| Error::State(self.path.clone(), e.into()))?;
+        std::fs::write(&self.path, s).map_err(|e| Error::State(self.path.clone(), e))
+    }
+}
+
+/// Key of a file in the state.
+fn key(p: &Path) -> String {
+    std::fs::canonicalize(p)
+        .unwrap_or_else(|_| p.to_path_buf())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Modification time of a file in nanoseconds since the Unix epoch.
+fn modified(p: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(p).ok()?.modified().ok()?;
+    let duration = modified.duration_since(UNIX_EPOCH).ok()?;
+    u64::try_from(duration.as_nanos()).ok()
+}
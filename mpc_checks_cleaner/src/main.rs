@@ -1,7 +1,9 @@
-use std::path::Path;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
 use clap::Parser;
+use rad_tools_common::get_log_level;
 use tracing::{info, Level, trace};
 
 const TDS: &str = "TDS";
@@ -23,9 +25,16 @@ struct Cli {
     /// Number of days the MPC checks are kept. Checks that are older, will be removed.
     #[arg(short, long, default_value_t = 365)]
     keep: i64,
+    /// Number of most recent MPC checks that are kept per machine and template, regardless of
+    /// their age.
+    #[arg(long, value_name = "N")]
+    keep_count: Option<usize>,
     /// Enable logging at DEBUG level.
     #[arg(long, default_value_t = false)]
     dry_run: bool,
+    /// Enable logging at INFO level.
+    #[arg(short, long, default_value_t = false)]
+    verbose: bool,
     /// Enable logging at DEBUG level.
     #[arg(long, default_value_t = false)]
     debug: bool,
@@ -36,13 +45,12 @@ struct Cli {
 
 fn main() {
     let cli = Cli::parse();
-    let level = if cli.trace {
-        Level::TRACE
-    } else if cli.debug {
-        Level::DEBUG
+    let default_level = if cli.dry_run {
+        Level::INFO
     } else {
         Level::WARN
     };
+    let level = get_log_level!(cli, default_level);
     tracing_subscriber::fmt()
         .with_thread_ids(true)
         .with_target(true)
@@ -82,7 +90,7 @@ fn main() {
                                     panic!("Expecting all directory entries in {:#?} to be a directory [with a machine ID as a name]", tds_path);
                                 }
                                 let mpc_checks_path = Path::join(&machine_id_entry.path(), MPC_CHECKS);
-                                clean_mpc_checks_path(&mpc_checks_path, cli.keep, cli.dry_run);
+                                clean_mpc_checks_path(&mpc_checks_path, cli.keep, cli.keep_count, cli.dry_run);
                             }
                             Err(e) => {
                                 panic!("Unable to get machine directories in {:#?}.\n{:#?}", tds_path, e);
@@ -123,10 +131,14 @@ fn text_box(s: &str) -> String {
 
 /// Cleans up the MPC checks path by removing checks that are older than a specified number of days.
 ///
+/// A check is kept if it is younger than `keep` days or if it is one of the `keep_count` most
+/// recent checks of its template in the directory.
+///
 /// # Arguments
 ///
 /// * `path` - The path to the directory containing the MPC checks (e.g. <va_transfer share>/TDS/<machine_id>/MPCChecks).
 /// * `keep` - The number of days to keep the checks.
+/// * `keep_count` - The number of most recent checks to keep per template, regardless of their age.
 /// * `dry_run` - Whether to perform a dry run or actually remove the checks.
 ///
 /// # Panics
@@ -134,8 +146,9 @@ fn text_box(s: &str) -> String {
 /// This function panics in the following situations:
 ///
 /// * Unable to read the MPC checks directory or its metadata.
-fn clean_mpc_checks_path(path: &Path, keep: i64, dry_run: bool) {
+fn clean_mpc_checks_path(path: &Path, keep: i64, keep_count: Option<usize>, dry_run: bool) {
     let now = Local::now().naive_local();
+    let mut checks = Vec::new();
     match std::fs::read_dir(path) {
         Ok(entries) => {
             for entry in entries {
@@ -149,11 +162,8 @@ fn clean_mpc_checks_path(path: &Path, keep: i64, dry_run: bool) {
                                 let os_fn = entry.file_name();
                                 let file_name = os_fn.to_string_lossy();
                                 let date_time = datetime_from_dir(&file_name);
-                                let duration = now.signed_duration_since(date_time);
-                                if duration.num_days() >= keep {
-                                    clean_mpc_path(&entry.path(), dry_run);
-                                    // std::fs::remove_dir_all(entry.path())?;
-                                }
+                                let template = template_from_dir(&file_name).to_string();
+                                checks.push((date_time, template, entry.path()));
                             }
                             Err(e) => {
                                 panic!("Unable to read MPC checks in {:#?}.\n{:#?}", path, e);
@@ -170,6 +180,47 @@ fn clean_mpc_checks_path(path: &Path, keep: i64, dry_run: bool) {
             panic!("Unable to read MPC checks in {:#?}.\n{:#?}", path, e);
         }
     }
+    for check_path in checks_to_clean(checks, now, keep, keep_count) {
+        clean_mpc_path(&check_path, dry_run);
+    }
+}
+
+/// Selects the MPC checks that need to be cleaned.
+///
+/// A check is kept if it is younger than `keep` days or, when `keep_count` is set, if it is one of
+/// the `keep_count` most recent checks of its template. Counting per template ensures that a
+/// template which runs rarely isn't removed completely by a template which runs often.
+///
+/// # Arguments
+///
+/// * `checks` - The date and time, and the template of each MPC check together with its path.
+/// * `now` - The reference date and time used to compute the age of a check.
+/// * `keep` - The number of days to keep the checks.
+/// * `keep_count` - The number of most recent checks to keep per template, regardless of their age.
+///
+/// # Returns
+///
+/// The paths of the checks that need to be cleaned, ordered from newest to oldest.
+fn checks_to_clean(
+    mut checks: Vec<(NaiveDateTime, String, PathBuf)>,
+    now: NaiveDateTime,
+    keep: i64,
+    keep_count: Option<usize>,
+) -> Vec<PathBuf> {
+    checks.sort_by_key(|c| std::cmp::Reverse(c.0));
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    checks
+        .into_iter()
+        .filter(|(date_time, template, _)| {
+            let count = counts.entry(template.clone()).or_default();
+            let rank = *count;
+            *count += 1;
+            let too_old = now.signed_duration_since(*date_time).num_days() >= keep;
+            let beyond_count = keep_count.is_none_or(|n| rank >= n);
+            too_old && beyond_count
+        })
+        .map(|(_, _, p)| p)
+        .collect()
 }
 
 /// Cleans an MPC check directory by removing all files except "Results.xml" and "Results.csv".
@@ -264,6 +315,26 @@ fn datetime_from_dir(s: &str) -> NaiveDateTime {
     )
 }
 
+/// Gets the template from a directory name.
+///
+/// The directory name should follow the format: `NDS-WKS-SN5783-2024-01-11-07-42-57-0000-BeamCheckTemplate6xFFF`,
+/// where the template is the last part (`BeamCheckTemplate6xFFF`).
+///
+/// # Arguments
+///
+/// * `s` - A string slice representing the directory name.
+///
+/// # Panics
+///
+/// This function will panic if the directory name does not have the correct format.
+fn template_from_dir(s: &str) -> &str {
+    let parts: Vec<&str> = s.split('-').collect();
+    if parts.len() != 11 {
+        panic!("Invalid directory name format detected in {:#?}", s);
+    }
+    parts[10]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -284,4 +355,129 @@ mod tests {
         let input = "NDS-WKS";
         let _ = datetime_from_dir(input);
     }
+
+    #[test]
+    fn test_checks_to_clean_keep_count() {
+        let now = NaiveDateTime::new(
+            NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(),
+            NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+        );
+        let checks: Vec<(NaiveDateTime, String, PathBuf)> = (1..=5)
+            .map(|day| {
+                (
+                    NaiveDateTime::new(
+                        NaiveDate::from_ymd_opt(2023, 1, day).unwrap(),
+                        NaiveTime::from_hms_opt(8, 0, 0).unwrap(),
+                    ),
+                    "BeamCheckTemplate6x".to_string(),
+                    PathBuf::from(format!("check{}", day)),
+                )
+            })
+            .collect();
+
+        // All checks are older than the number of days to keep.
+        let cleaned = checks_to_clean(checks.clone(), now, 30, None);
+        assert_eq!(5, cleaned.len());
+
+        // Only the newest checks survive.
+        let cleaned = checks_to_clean(checks.clone(), now, 30, Some(2));
+        assert_eq!(
+            vec![
+                PathBuf::from("check3"),
+                PathBuf::from("check2"),
+                PathBuf::from("check1")
+            ],
+            cleaned
+        );
+
+        // A check is kept if either rule keeps it.
+        let cleaned = checks_to_clean(checks, now, 600, Some(2));
+        assert!(cleaned.is_empty());
+    }
+
+    #[test]
+    fn test_clean_mpc_checks_path_keep_count() {
+        let dir = std::env::temp_dir().join("rad_tools_mpc_checks_keep_count");
+        if dir.is_dir() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        let names: Vec<String> = (1..=4)
+            .map(|day| {
+                format!(
+                    "NDS-WKS-SN5783-2020-01-0{}-07-42-57-0000-BeamCheckTemplate6xFFF",
+                    day
+                )
+            })
+            .collect();
+        for name in &names {
+            let check = dir.join(name);
+            std::fs::create_dir_all(&check).unwrap();
+            std::fs::write(check.join("Results.csv"), "results").unwrap();
+            std::fs::write(check.join("image.xim"), "image").unwrap();
+        }
+
+        clean_mpc_checks_path(&dir, 365, Some(2), false);
+
+        for (i, name) in names.iter().enumerate() {
+            let check = dir.join(name);
+            assert!(check.join("Results.csv").is_file());
+            // Only the two most recent checks keep their images.
+            assert_eq!(i >= 2, check.join("image.xim").is_file());
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_template_from_dir() {
+        let input = "NDS-WKS-SN5783-2024-01-11-07-42-57-0000-BeamCheckTemplate6xFFF";
+        assert_eq!("BeamCheckTemplate6xFFF", template_from_dir(input));
+    }
+
+    #[test]
+    fn test_clean_mpc_checks_path_keep_count_per_template() {
+        let dir = std::env::temp_dir().join("rad_tools_mpc_checks_keep_count_per_template");
+        if dir.is_dir() {
+            std::fs::remove_dir_all(&dir).unwrap();
+        }
+        // The 6x template runs daily, the 6xFFF template only once a month.
+        let daily: Vec<String> = (1..=6)
+            .map(|day| {
+                format!(
+                    "NDS-WKS-SN5783-2020-03-0{}-07-42-57-0000-BeamCheckTemplate6x",
+                    day
+                )
+            })
+            .collect();
+        let monthly: Vec<String> = (1..=3)
+            .map(|month| {
+                format!(
+                    "NDS-WKS-SN5783-2020-0{}-01-08-00-00-0000-BeamCheckTemplate6xFFF",
+                    month
+                )
+            })
+            .collect();
+        for name in daily.iter().chain(monthly.iter()) {
+            let check = dir.join(name);
+            std::fs::create_dir_all(&check).unwrap();
+            std::fs::write(check.join("Results.csv"), "results").unwrap();
+            std::fs::write(check.join("image.xim"), "image").unwrap();
+        }
+
+        clean_mpc_checks_path(&dir, 365, Some(2), false);
+
+        // The two most recent checks of each template keep their images.
+        for (i, name) in daily.iter().enumerate() {
+            let check = dir.join(name);
+            assert!(check.join("Results.csv").is_file());
+            assert_eq!(i >= 4, check.join("image.xim").is_file());
+        }
+        for (i, name) in monthly.iter().enumerate() {
+            let check = dir.join(name);
+            assert!(check.join("Results.csv").is_file());
+            assert_eq!(i >= 1, check.join("image.xim").is_file());
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -2,9 +2,16 @@
 use std::path::{Path, PathBuf};
 #[cfg(windows)]
 use std::process::{Command, exit};
+#[cfg(windows)]
+use std::sync::Arc;
+#[cfg(windows)]
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use clap::Parser;
 
+#[cfg_attr(not(windows), allow(dead_code))]
+mod verify;
+
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about = "
 A command line application that synchronizes the data from a source directory to a destination directory. The data is synchronized in one way / direction, it doesn't create a mirror between two directories.
@@ -19,6 +26,12 @@ struct Cli {
     /// Enable log
     #[arg(short, long, default_value_t = false)]
     log: bool,
+    /// Verify that each synchronized file has the same size as the source file
+    #[arg(long, default_value_t = false)]
+    verify: bool,
+    /// Verify that each synchronized file has the same size and SHA-256 checksum as the source file
+    #[arg(long, default_value_t = false)]
+    verify_hash: bool,
 }
 
 fn main() {
@@ -57,20 +70,56 @@ fn one_way_sync(cli: &Cli) {
         cmd.arg("/v");
         cmd.arg(format!("/UNILOG+:{}", &logfile.as_os_str().to_str().unwrap()));
     }
+    // Robocopy receives the Ctrl-C / termination signal itself. The handler keeps this process
+    // alive until robocopy has stopped, so the synchronization isn't abandoned halfway.
+    let stop = Arc::new(AtomicBool::new(false));
+    let handler_stop = stop.clone();
+    ctrlc::set_handler(move || handler_stop.store(true, Ordering::SeqCst))
+        .expect("Unable to set the signal handler.");
     let exit_status = cmd.status().expect("Something went wrong while running robocopy.");
+    if stop.load(Ordering::SeqCst) {
+        eprintln!("Synchronization was stopped before robocopy finished.");
+        exit(1);
+    }
     match exit_status.code() {
         None => {}
         Some(code) => {
-            if code == 8 {
+            // Robocopy exit codes of 8 and higher mean that at least one file failed to copy.
+            if code >= 8 {
                 exit(code);
-            } else {
-                exit(0);
             }
         }
     }
+    // Robocopy's exit code doesn't guarantee the integrity of the copied files.
+    if cli.verify || cli.verify_hash {
+        verify_sync(cli);
+    }
     exit(0);
 }
 
+/// Verify the synchronized files, and exit with a non-zero exit code if one doesn't match.
+///
+/// # Arguments
+///
+/// * `cli`: A reference to a `Cli` struct containing the necessary parameters for the sync operation.
+#[cfg(windows)]
+fn verify_sync(cli: &Cli) {
+    match verify::verify(&cli.src, &cli.dest, cli.verify_hash) {
+        Ok(mismatches) => {
+            if !mismatches.is_empty() {
+                for m in &mismatches {
+                    eprintln!("Verification failed: {}", m);
+                }
+                exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("Unable to verify the synchronized files: {}", e);
+            exit(1);
+        }
+    }
+}
+
 #[cfg(not(windows))]
 fn one_way_sync(_cli: &Cli) {
     println!("Functionality is not implemented on platforms other than Windows");
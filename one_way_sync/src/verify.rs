@@ -0,0 +1,143 @@
+//! Verification of the synchronized files.
+//!
+//! After the synchronization, each file in the source is compared with the file at the same
+//! relative path in the destination: by size, and optionally by SHA-256 checksum.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use rad_tools_common::fs::sha256;
+use walkdir::WalkDir;
+
+/// A source file of which the copy in the destination doesn't match.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mismatch {
+    /// The destination file doesn't exist.
+    Missing(PathBuf),
+    /// The size of the destination file differs from the source file.
+    Size {
+        dst: PathBuf,
+        src_len: u64,
+        dst_len: u64,
+    },
+    /// The checksum of the destination file differs from the source file.
+    Hash(PathBuf),
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mismatch::Missing(dst) => write!(f, "{:#?} is missing", dst),
+            Mismatch::Size {
+                dst,
+                src_len,
+                dst_len,
+            } => write!(
+                f,
+                "{:#?} has a size of {} bytes, expected {} bytes",
+                dst, dst_len, src_len
+            ),
+            Mismatch::Hash(dst) => write!(f, "{:#?} has a different SHA-256 checksum", dst),
+        }
+    }
+}
+
+/// Verify that each file in the source exists in the destination with the same content.
+///
+/// # Arguments
+///
+/// * `src`: source file or directory
+/// * `dest`: destination directory
+/// * `hash`: also compare the SHA-256 checksums of the files
+///
+/// returns: std::io::Result<Vec<Mismatch>>
+/// The files that don't match, or an error if the source or destination couldn't be read.
+pub fn verify<P, Q>(src: P, dest: Q, hash: bool) -> std::io::Result<Vec<Mismatch>>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let src = src.as_ref();
+    let dest = dest.as_ref();
+    let mut mismatches = vec![];
+    if src.is_file() {
+        let dst = dest.join(src.file_name().unwrap());
+        if let Some(m) = verify_file(src, &dst, hash)? {
+            mismatches.push(m);
+        }
+        return Ok(mismatches);
+    }
+    for entry in WalkDir::new(src) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel_path = entry.path().strip_prefix(src).unwrap();
+        if let Some(m) = verify_file(entry.path(), &dest.join(rel_path), hash)? {
+            mismatches.push(m);
+        }
+    }
+    Ok(mismatches)
+}
+
+/// Compare a source file with its copy.
+fn verify_file(src: &Path, dst: &Path, hash: bool) -> std::io::Result<Option<Mismatch>> {
+    if !dst.is_file() {
+        return Ok(Some(Mismatch::Missing(dst.to_path_buf())));
+    }
+    let src_len = src.metadata()?.len();
+    let dst_len = dst.metadata()?.len();
+    if src_len != dst_len {
+        return Ok(Some(Mismatch::Size {
+            dst: dst.to_path_buf(),
+            src_len,
+            dst_len,
+        }));
+    }
+    if hash && sha256(src)? != sha256(dst)? {
+        return Ok(Some(Mismatch::Hash(dst.to_path_buf())));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{verify, Mismatch};
+
+    #[test]
+    fn verify_detects_corruption() {
+        let temp_dir = std::env::temp_dir().join("rad_tools_one_way_sync_verify");
+        let src = temp_dir.join("src");
+        let dest = temp_dir.join("dest");
+        if temp_dir.is_dir() {
+            std::fs::remove_dir_all(&temp_dir).unwrap();
+        }
+        for dir in [&src, &dest] {
+            std::fs::create_dir_all(dir.join("sub")).unwrap();
+            std::fs::write(dir.join("a.txt"), "abc").unwrap();
+            std::fs::write(dir.join("sub").join("b.txt"), "def").unwrap();
+        }
+        assert!(verify(&src, &dest, true).unwrap().is_empty());
+
+        // Same size, different content: only detected by the checksum.
+        std::fs::write(dest.join("sub").join("b.txt"), "xyz").unwrap();
+        assert!(verify(&src, &dest, false).unwrap().is_empty());
+        assert_eq!(
+            vec![Mismatch::Hash(dest.join("sub").join("b.txt"))],
+            verify(&src, &dest, true).unwrap()
+        );
+
+        std::fs::write(dest.join("a.txt"), "abcd").unwrap();
+        std::fs::remove_file(dest.join("sub").join("b.txt")).unwrap();
+        let mismatches = verify(&src, &dest, false).unwrap();
+        assert_eq!(2, mismatches.len());
+        assert!(mismatches.contains(&Mismatch::Size {
+            dst: dest.join("a.txt"),
+            src_len: 3,
+            dst_len: 4,
+        }));
+        assert!(mismatches.contains(&Mismatch::Missing(dest.join("sub").join("b.txt"))));
+
+        std::fs::remove_dir_all(&temp_dir).unwrap();
+    }
+}